@@ -55,6 +55,7 @@ pub(crate) enum StructureType {
     Array,
     ArrayLastOpt,
     MapInt,
+    MapText,
 }
 
 impl FromStr for StructureType {
@@ -66,6 +67,7 @@ impl FromStr for StructureType {
             "array" => Ok(StructureType::Array),
             "array_lastopt" => Ok(StructureType::ArrayLastOpt),
             "mapint" => Ok(StructureType::MapInt),
+            "maptext" => Ok(StructureType::MapText),
             _ => Err(format!("unrecognized structure type {}", s)),
         }
     }
@@ -76,6 +78,11 @@ pub(crate) enum EnumType {
     TagVariant,
     EnumInt,
     EnumType,
+    StrVariant,
+    // bare text name for enums without fields
+    StrEnum,
+    // bare integer for variants without fields, tagvariant array otherwise
+    Compact,
 }
 
 impl FromStr for EnumType {
@@ -86,6 +93,9 @@ impl FromStr for EnumType {
             "tagvariant" => Ok(EnumType::TagVariant),
             "enumint" => Ok(EnumType::EnumInt),
             "enumtype" => Ok(EnumType::EnumType),
+            "strvariant" => Ok(EnumType::StrVariant),
+            "strenum" => Ok(EnumType::StrEnum),
+            "compact" => Ok(EnumType::Compact),
             _ => Err(format!("unrecognized enum type {}", s)),
         }
     }
@@ -99,6 +109,9 @@ pub(crate) enum Attr {
     VariantStartsAt(usize),
     MapStartsAt(usize),
     SkipKey(u64),
+    Borrowed,
+    Transparent,
+    Accept(Vec<StructureType>),
 }
 
 fn parse_meta_list(meta: &Meta) -> &syn::MetaList {
@@ -146,6 +159,22 @@ pub(crate) fn parse_attr(meta: &Meta) -> Vec<Attr> {
                 let lit: syn::LitInt = value.parse()?;
                 output.push(Attr::VariantStartsAt(parse_int(&lit) as usize));
                 Ok(())
+            } else if meta.path.is_ident("borrowed") {
+                output.push(Attr::Borrowed);
+                Ok(())
+            } else if meta.path.is_ident("transparent") {
+                output.push(Attr::Transparent);
+                Ok(())
+            } else if meta.path.is_ident("accept") {
+                let value = meta.value()?;
+                let lit: syn::LitStr = value.parse()?;
+                let struct_types = lit
+                    .value()
+                    .split(',')
+                    .map(|s| StructureType::from_str(s.trim()).expect("Valid struct type"))
+                    .collect();
+                output.push(Attr::Accept(struct_types));
+                Ok(())
             } else if meta.path.is_ident("skipkey") {
                 let value = meta.value()?;
                 let lit: syn::LitInt = value.parse()?;
@@ -165,6 +194,13 @@ pub(crate) enum FieldAttr {
     Optional,
     Mandatory,
     CborType(FieldCborType),
+    Bstr,
+    Verbatim,
+    Rename(String),
+    Rest,
+    MapStartsAt(usize),
+    SkipKey(u64),
+    Key(i64),
 }
 
 #[derive(Clone)]
@@ -173,6 +209,14 @@ pub(crate) struct FieldAttrs {
     pub(crate) mandatory_map: bool,
     pub(crate) optional_vec: bool,
     pub(crate) cbor_type: Option<FieldCborType>,
+    pub(crate) bstr: bool,
+    pub(crate) verbatim: bool,
+    pub(crate) rename: Option<String>,
+    pub(crate) rest: bool,
+    pub(crate) key: Option<i64>,
+    // the map keys of a mapint enum variant
+    pub(crate) map_starts_at: Option<usize>,
+    pub(crate) skips: Vec<u64>,
 }
 
 impl Default for FieldAttrs {
@@ -182,6 +226,13 @@ impl Default for FieldAttrs {
             mandatory_map: false,
             optional_vec: false,
             cbor_type: None,
+            bstr: false,
+            verbatim: false,
+            rename: None,
+            rest: false,
+            key: None,
+            map_starts_at: None,
+            skips: Vec::new(),
         }
     }
 }
@@ -193,6 +244,13 @@ impl FieldAttrs {
             FieldAttr::Mandatory => self.mandatory_map = true,
             FieldAttr::Optional => self.optional_vec = true,
             FieldAttr::CborType(ty) => self.cbor_type = Some(*ty),
+            FieldAttr::Bstr => self.bstr = true,
+            FieldAttr::Verbatim => self.verbatim = true,
+            FieldAttr::Rename(name) => self.rename = Some(name.clone()),
+            FieldAttr::Rest => self.rest = true,
+            FieldAttr::Key(key) => self.key = Some(*key),
+            FieldAttr::MapStartsAt(start) => self.map_starts_at = Some(*start),
+            FieldAttr::SkipKey(skip) => self.skips.push(*skip),
         }
         self
     }
@@ -223,6 +281,35 @@ pub(crate) fn parse_field_attr(meta: &Meta) -> Vec<FieldAttr> {
             } else if meta.path.is_ident("optional") {
                 output.push(FieldAttr::Optional);
                 Ok(())
+            } else if meta.path.is_ident("bstr") {
+                output.push(FieldAttr::Bstr);
+                Ok(())
+            } else if meta.path.is_ident("verbatim") {
+                output.push(FieldAttr::Verbatim);
+                Ok(())
+            } else if meta.path.is_ident("rest") {
+                output.push(FieldAttr::Rest);
+                Ok(())
+            } else if meta.path.is_ident("key") {
+                let value = meta.value()?;
+                let lit: syn::LitInt = value.parse()?;
+                output.push(FieldAttr::Key(lit.base10_parse()?));
+                Ok(())
+            } else if meta.path.is_ident("rename") {
+                let value = meta.value()?;
+                let s: syn::LitStr = value.parse()?;
+                output.push(FieldAttr::Rename(s.value()));
+                Ok(())
+            } else if meta.path.is_ident("map_starts_at") {
+                let value = meta.value()?;
+                let lit: syn::LitInt = value.parse()?;
+                output.push(FieldAttr::MapStartsAt(parse_int(&lit) as usize));
+                Ok(())
+            } else if meta.path.is_ident("skipkey") {
+                let value = meta.value()?;
+                let lit: syn::LitInt = value.parse()?;
+                output.push(FieldAttr::SkipKey(parse_int(&lit)));
+                Ok(())
             } else {
                 Err(meta.error("unsupported attribute"))
             }
@@ -1,6 +1,6 @@
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::Ident;
+use syn::{Generics, Ident, Lifetime, Type};
 
 pub(crate) fn token_impl_deserializer(class_name: &Ident, body: TokenStream) -> TokenStream {
     quote! {
@@ -12,12 +12,44 @@ pub(crate) fn token_impl_deserializer(class_name: &Ident, body: TokenStream) ->
     }
 }
 
-pub(crate) fn token_impl_serializer(class_name: &Ident, body: TokenStream) -> TokenStream {
+pub(crate) fn token_impl_borrowed_deserializer(
+    class_name: &Ident,
+    lifetime: &Lifetime,
+    body: TokenStream,
+) -> TokenStream {
     quote! {
-        impl ::cbored::Encode for #class_name {
+        impl<#lifetime> ::cbored::DecodeBorrowed<#lifetime> for #class_name<#lifetime> {
+            fn decode_borrowed(reader: &mut ::cbored::Reader<#lifetime>) -> Result<Self, ::cbored::DecodeError> {
+                #body
+            }
+        }
+    }
+}
+
+pub(crate) fn token_impl_serializer(
+    class_name: &Ident,
+    generics: &Generics,
+    body: TokenStream,
+) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::cbored::Encode for #class_name #ty_generics #where_clause {
             fn encode(&self, writer: &mut ::cbored::Writer) {
                 #body
             }
         }
     }
 }
+
+// check if a type is syntactically an `Option<...>`
+pub(crate) fn is_option_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == "Option")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
@@ -15,18 +15,20 @@ pub fn derive_cbor_repr(input: TokenStream) -> TokenStream {
     // Parse type (struct/enum)
     let ast = syn::parse_macro_input!(input as DeriveInput);
 
-    let has_generics = ast.generics.params.len() > 0;
-    if has_generics {
-        panic!("cannot handle types with generics")
-    }
-
     // Gather the cborrepr attributes as Meta
     let attrs = get_my_attributes(&ast.attrs).collect::<Vec<_>>();
 
-    // either do struct or enum handling
+    // either do struct or enum handling, generics are only supported
+    // for the lifetime of borrowed structure
     match ast.data {
-        Data::Struct(st) => derive_struct(ast.ident, &attrs, st),
-        Data::Enum(e) => derive_enum(ast.ident, &attrs, e),
+        Data::Struct(st) => derive_struct(ast.ident, &ast.generics, &attrs, st),
+        Data::Enum(e) => {
+            let has_generics = ast.generics.params.len() > 0;
+            if has_generics {
+                panic!("cannot handle types with generics")
+            }
+            derive_enum(ast.ident, &attrs, e)
+        }
         Data::Union(_) => panic!("Union not supported"),
     }
 }
@@ -1,16 +1,20 @@
 use proc_macro::TokenStream;
 
 use quote::quote;
-use syn::{DataStruct, Fields, FieldsNamed, FieldsUnnamed, Ident, Meta};
+use syn::{DataStruct, Fields, FieldsNamed, FieldsUnnamed, GenericParam, Generics, Ident, Meta};
 
 use super::attr::*;
 use super::common::*;
 
+#[derive(Clone)]
 pub(crate) struct StructAttrs {
     structure_type: StructureType,
     starts_at: usize,
     tag: Option<u64>,
     skips: Vec<u64>,
+    borrowed: bool,
+    transparent: bool,
+    accept: Vec<StructureType>,
 }
 
 impl Default for StructAttrs {
@@ -20,6 +24,9 @@ impl Default for StructAttrs {
             starts_at: 0,
             tag: None,
             skips: Vec::new(),
+            borrowed: false,
+            transparent: false,
+            accept: Vec::new(),
         }
     }
 }
@@ -36,15 +43,22 @@ impl StructAttrs {
                 panic!("structure does not support enum type attribute")
             }
             Attr::SkipKey(skip) => self.skips.push(*skip),
+            Attr::Borrowed => self.borrowed = true,
+            // a transparent structure is a flat structure of one field
+            Attr::Transparent => {
+                self.structure_type = StructureType::Flat;
+                self.transparent = true;
+            }
+            Attr::Accept(tys) => self.accept = tys.clone(),
         }
         self
     }
 }
 
 pub struct Field {
-    index: usize,
-    name: Ident,
-    attrs: FieldAttrs,
+    pub(crate) index: usize,
+    pub(crate) name: Ident,
+    pub(crate) attrs: FieldAttrs,
 }
 
 pub(crate) enum StructOutput {
@@ -63,15 +77,16 @@ impl StructOutput {
     }
 }
 
-fn get_struct_naming(fields: &Fields) -> StructOutput {
-    fn attrs(attrs: &Vec<syn::Attribute>) -> FieldAttrs {
-        get_my_attributes(attrs)
-            .map(|a| parse_field_attr(a))
-            .fold(FieldAttrs::default(), |acc, y| {
-                y.iter().fold(acc, |acc, y| acc.merge(y))
-            })
-    }
+// merge all the cborrepr attributes of a field (or of a variant)
+pub(crate) fn field_attrs(attrs: &Vec<syn::Attribute>) -> FieldAttrs {
+    get_my_attributes(attrs)
+        .map(|a| parse_field_attr(a))
+        .fold(FieldAttrs::default(), |acc, y| {
+            y.iter().fold(acc, |acc, y| acc.merge(y))
+        })
+}
 
+fn get_struct_naming(fields: &Fields) -> StructOutput {
     match fields {
         Fields::Named(FieldsNamed {
             brace_token: _,
@@ -83,7 +98,7 @@ fn get_struct_naming(fields: &Fields) -> StructOutput {
                 .map(|(index, field)| Field {
                     index,
                     name: field.ident.clone().unwrap(),
-                    attrs: attrs(&field.attrs),
+                    attrs: field_attrs(&field.attrs),
                 })
                 .collect::<Vec<_>>();
             StructOutput::Named(names)
@@ -98,17 +113,56 @@ fn get_struct_naming(fields: &Fields) -> StructOutput {
                 .map(|(i, fi)| Field {
                     index: i,
                     name: quote::format_ident!("field{}", i),
-                    attrs: attrs(&fi.attrs),
+                    attrs: field_attrs(&fi.attrs),
                 })
                 .collect();
             StructOutput::Unnamed(indexes)
         }
-        Fields::Unit => panic!("field unit not supported"),
+        // a unit structure is handled as a record without fields, as `Name {}`
+        // is a valid expression and pattern for it
+        Fields::Unit => StructOutput::Named(Vec::new()),
+    }
+}
+
+// encode a field value (given by reference), wrapped as a CBOR bytes if the field is `bstr`,
+// or written as is if the field is `verbatim`
+pub(crate) fn field_encode(
+    field_attrs: &FieldAttrs,
+    value: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if field_attrs.bstr && field_attrs.verbatim {
+        panic!("field cannot be both bstr and verbatim")
+    }
+    if field_attrs.verbatim {
+        quote! { <::cbored::CborData as ::cbored::Encode>::encode(#value, writer) }
+    } else if field_attrs.bstr {
+        quote! { writer.bytes(&::cbored::Bytes::from_slice(&::cbored::encode_to_bytes(#value))) }
+    } else {
+        quote! { writer.encode(#value) }
+    }
+}
+
+// decode a field from a reader or a slice, unwrapping the CBOR bytes first if the field is `bstr`,
+// or capturing the raw CBOR element if the field is `verbatim`
+pub(crate) fn field_decode(
+    field_attrs: &FieldAttrs,
+    decode: &proc_macro2::TokenStream,
+    source: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if field_attrs.verbatim {
+        quote! { #source.decode::<::cbored::CborData>() }
+    } else if field_attrs.bstr {
+        quote! {
+            #source.decode::<Vec<u8>>().and_then(|bytes| ::cbored::decode_from_bytes(&bytes))
+        }
+    } else {
+        quote! { #source.#decode() }
     }
 }
 
 pub(crate) fn derive_struct_se(
     name: &Ident,
+    generics: &Generics,
     attrs: &StructAttrs,
     st: &DataStruct,
 ) -> proc_macro2::TokenStream {
@@ -130,18 +184,20 @@ pub(crate) fn derive_struct_se(
                 let Field {
                     index: field_idx,
                     name: field_name,
-                    attrs: _,
+                    attrs: field_attrs,
                 } = &field;
                 let field_body = if last_is_opt && *field_idx == fields.len() - 1 {
+                    let encode = field_encode(field_attrs, quote! { v });
                     quote! {
                         match &self.#field_name {
                             None => (),
-                            Some(v) => writer.encode(v),
+                            Some(v) => #encode,
                         };
                     }
                 } else {
+                    let encode = field_encode(field_attrs, quote! { &self.#field_name });
                     quote! {
-                        writer.encode(&self.#field_name);
+                        #encode;
                     }
                 };
                 field_bodies.push(field_body);
@@ -155,10 +211,11 @@ pub(crate) fn derive_struct_se(
         StructOutput::Unnamed(fields) => {
             let mut se_bodies = Vec::new();
 
-            for (field_idx, _field_name) in fields.iter().enumerate() {
+            for (field_idx, field) in fields.iter().enumerate() {
                 let idx = syn::Index::from(field_idx);
+                let encode = field_encode(&field.attrs, quote! { &self.#idx });
                 let se_body = quote! {
-                    writer.encode(&self.#idx);
+                    #encode;
                 };
                 se_bodies.push(se_body);
             }
@@ -195,7 +252,7 @@ pub(crate) fn derive_struct_se(
                     });
                 }
             }
-            StructureType::MapInt => {
+            StructureType::MapInt | StructureType::MapText => {
                 let mut fixed = 0u64;
                 let mut len_for_optionals = Vec::new();
                 let mut fields_write_map = Vec::new();
@@ -206,36 +263,50 @@ pub(crate) fn derive_struct_se(
                     }
                     // Generate output for a standard record
                     StructOutput::Named(field_elements) => {
-                        let mut rel_index = attrs.starts_at as u64;
+                        let mut keys = map_int_keys(attrs.starts_at, &attrs.skips, &field_elements)
+                            .into_iter();
                         for field in field_elements.iter() {
                             let Field {
-                                index: field_index,
+                                index: _,
                                 name: field_name,
                                 attrs: field_attrs,
                             } = &field;
-                            loop {
-                                let abs_index = *field_index as u64 + rel_index;
-                                if attrs.skips.iter().any(|v| *v == abs_index) {
-                                    rel_index += 1;
-                                } else {
-                                    break;
-                                }
+                            // the catch-all entries are written after the known keys
+                            if field_attrs.rest {
+                                fields_write_map.push(quote! {
+                                    for (key, value) in self.#field_name.iter() {
+                                        writer.integer(i128::from(*key)).expect("key in the CBOR integer range");
+                                        <::cbored::CborData as ::cbored::Encode>::encode(value, writer);
+                                    }
+                                });
+                                len_for_optionals.push(quote! {
+                                    + self.#field_name.len() as u64
+                                });
+                                continue;
                             }
-                            let abs_index = *field_index as u64 + rel_index;
+                            let write_key = if attrs.structure_type == StructureType::MapText {
+                                let key = map_text_key(field);
+                                quote! { writer.encode(#key); }
+                            } else {
+                                map_int_key_encode(keys.next().expect("key of mapint field"))
+                            };
 
                             if field_attrs.mandatory_map {
+                                let encode =
+                                    field_encode(field_attrs, quote! { &self.#field_name });
                                 fields_write_map.push(quote! {
-                                    writer.encode(&(#abs_index as u64));
-                                    writer.encode(&self.#field_name);
+                                    #write_key
+                                    #encode;
                                 });
                                 fixed += 1;
                             } else {
+                                let encode = field_encode(field_attrs, quote! { value });
                                 fields_write_map.push(quote! {
                                     match &self.#field_name {
                                         None => {},
                                         Some(value) => {
-                                            writer.encode(&(#abs_index as u64));
-                                            writer.encode(value);
+                                            #write_key
+                                            #encode;
                                         }
                                     }
                                 });
@@ -272,23 +343,117 @@ pub(crate) fn derive_struct_se(
         }
     };
 
-    token_impl_serializer(&name, se_body)
+    token_impl_serializer(&name, generics, se_body)
 }
 
 pub enum DeStructure {
     Flat,
     Array { last_optional: bool },
     MapInt,
+    MapText,
+}
+
+// write the integer key of a field in a mapint structure, as a CBOR negative integer
+// for negative keys
+pub(crate) fn map_int_key_encode(key: i64) -> proc_macro2::TokenStream {
+    if key < 0 {
+        let n = (-1 - key) as u64;
+        quote! { writer.negative(::cbored::Negative::canonical(#n)); }
+    } else {
+        let p = key as u64;
+        quote! { writer.encode(&(#p as u64)); }
+    }
 }
 
-// derive CBOR serializer and deserialize for a struct (either tuple or record)
+// the text key of a field in a maptext structure, which is the field name unless renamed
+fn map_text_key(field: &Field) -> String {
+    field
+        .attrs
+        .rename
+        .clone()
+        .unwrap_or_else(|| field.name.to_string())
+}
+
+// derive CBOR deserializer for a struct (either tuple or record)
+//
+// when the structure accept multiple representations, the decoder peek the CBOR type
+// and dispatch to the decoding body of the matching representation
 pub(crate) fn derive_struct_de(
+    name: &Ident,
+    generics: &Generics,
+    attrs: &StructAttrs,
+    st: &DataStruct,
+) -> proc_macro2::TokenStream {
+    let de_body = if attrs.accept.is_empty() {
+        derive_struct_de_body(name, attrs, st)
+    } else {
+        if !attrs.accept.contains(&attrs.structure_type) {
+            panic!("accept need to contain the structure type")
+        }
+        if attrs.tag.is_some() {
+            panic!("accept not supported with tag")
+        }
+        let mut array_body = None;
+        let mut map_body = None;
+        for ty in attrs.accept.iter() {
+            let body = derive_struct_de_body(
+                name,
+                &StructAttrs {
+                    structure_type: *ty,
+                    ..attrs.clone()
+                },
+                st,
+            );
+            let slot = match ty {
+                StructureType::Array | StructureType::ArrayLastOpt => &mut array_body,
+                StructureType::MapInt | StructureType::MapText => &mut map_body,
+                StructureType::Flat => panic!("accept does not support flat structure"),
+            };
+            if slot.replace(body).is_some() {
+                panic!("accept cannot contain multiple array or map structures")
+            }
+        }
+        let array_arm = array_body.map(|body| quote! { ::cbored::Type::Array => { #body } });
+        let map_arm = map_body.map(|body| quote! { ::cbored::Type::Map => { #body } });
+        quote! {
+            match reader.peek_type().map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.context::<Self>())? {
+                #array_arm
+                #map_arm
+                got => Err(::cbored::DecodeErrorKind::ReaderError(::cbored::ReaderError::WrongExpectedTypes {
+                    expected: &[::cbored::Type::Array, ::cbored::Type::Map],
+                    got,
+                }).context::<Self>()),
+            }
+        }
+    };
+
+    if attrs.borrowed {
+        let lifetime = match generics.params.first() {
+            Some(GenericParam::Lifetime(param)) if generics.params.len() == 1 => &param.lifetime,
+            _ => panic!("borrowed structure need exactly one lifetime parameter"),
+        };
+        token_impl_borrowed_deserializer(name, lifetime, de_body)
+    } else {
+        token_impl_deserializer(&name, de_body)
+    }
+}
+
+// create the decoding body of a struct for the structure type of the attributes
+fn derive_struct_de_body(
     name: &Ident,
     attrs: &StructAttrs,
     st: &DataStruct,
 ) -> proc_macro2::TokenStream {
     let fields = &st.fields;
 
+    // borrowed structure decode their fields with `decode_borrowed`, which allow fields
+    // such as `&'a str` or `&'a [u8]` to borrow from the reader data
+    let decode = if attrs.borrowed {
+        quote! { decode_borrowed }
+    } else {
+        quote! { decode }
+    };
+
     let field_names = get_struct_naming(fields);
     let nb_items = field_names.len();
 
@@ -337,7 +502,7 @@ pub(crate) fn derive_struct_de(
             let r = if tag_structure {
                 quote! {
                     #tag_wrapper
-                    let array = tag.read_data(|reader| reader.array()).map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.context::<Self>())?;
+                    let array = reader.read_nested(tag.data(), |reader| reader.array()).map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.context::<Self>())?;
                 }
             } else {
                 quote! { let array = reader.array().map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.context::<Self>())?; }
@@ -368,20 +533,25 @@ pub(crate) fn derive_struct_de(
                 )
             }
         }
-        StructureType::MapInt => {
+        StructureType::MapInt | StructureType::MapText => {
             let r = if tag_structure {
                 quote! {
                     #tag_wrapper
-                    let map = tag.read_data(|reader| reader.map()).map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.context::<Self>())?;
+                    let map = reader.read_nested(tag.data(), |reader| reader.map()).map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.context::<Self>())?;
                 }
             } else {
                 quote! { let map = reader.map().map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.context::<Self>())?; }
             };
+            let structure = if attrs.structure_type == StructureType::MapText {
+                DeStructure::MapText
+            } else {
+                DeStructure::MapInt
+            };
             (
                 quote! {
                     #r
                 },
-                DeStructure::MapInt,
+                structure,
             )
         }
     };
@@ -391,7 +561,7 @@ pub(crate) fn derive_struct_de(
     let mut de_bodies = Vec::new();
 
     // create the quote that deserialize all the elements one by one
-    let de_body = match field_names {
+    match field_names {
         // Generate output for a standard record
         StructOutput::Named(fields) => {
             let field_names = fields
@@ -409,29 +579,48 @@ pub(crate) fn derive_struct_de(
                         } = &field;
                         let field_index = *field_index;
                         let field_name_str = format!("{}", field_name);
-                        let de_body = if field_attrs.variant == FieldVariantType::Vec {
+                        let de_body = if field_attrs.variant == FieldVariantType::Vec
+                            && attrs.borrowed
+                        {
+                            // iterate by index, as the array iterator only borrow from the local array
+                            quote! {
+                                let #field_name = {
+                                    let mut r = reader.nested_reader(array[#field_index]);
+                                    let inner = r.array().map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.context::<Self>())?;
+                                    let vec = (0..inner.len())
+                                        .map(|i| r.nested_reader(inner[i]).decode_borrowed())
+                                        .collect::<Result<Vec<_>, ::cbored::DecodeError>>()?;
+                                    vec
+                                };
+                            }
+                        } else if field_attrs.variant == FieldVariantType::Vec {
                             quote! {
                                 let #field_name = {
-                                    let mut r = array[#field_index].reader();
-                                    let vec = r.array().map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.push::<Self>())?
-                                        .iter()
-                                        .map(|mut r| r.decode())
+                                    let mut r = reader.nested_reader(array[#field_index]);
+                                    let inner = r.array().map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.push::<Self>())?;
+                                    let vec = (0..inner.len())
+                                        .map(|i| r.nested_reader(inner[i]).decode())
                                         .collect::<Result<Vec<_>, ::cbored::DecodeError>>()?;
                                     vec
                                 };
                             }
                         } else {
+                            let decode = field_decode(
+                                field_attrs,
+                                &decode,
+                                quote! { reader.nested_reader(array[#field_index]) },
+                            );
                             if last_optional && field_index == fields.len() - 1 {
                                 quote! {
                                     let #field_name = if array.len() == #field_index + 1 {
-                                        Some(array[#field_index].decode().map_err(|e| e.push_str(#field_name_str).push::<Self>())?)
+                                        Some(#decode.map_err(|e| e.push_str(#field_name_str).push::<Self>())?)
                                     } else {
                                         None
                                     };
                                 }
                             } else {
                                 quote! {
-                                    let #field_name = array[#field_index].decode().map_err(|e| e.push_str(#field_name_str).push::<Self>())?;
+                                    let #field_name = #decode.map_err(|e| e.push_str(#field_name_str).push::<Self>())?;
                                 }
                             }
                         };
@@ -445,40 +634,40 @@ pub(crate) fn derive_struct_de(
                     }
                 }
                 DeStructure::MapInt => {
-                    if nb_items > 64 {
-                        panic!("cannot support structure with more than 64 fields");
-                    }
-
                     let mut keydefs = Vec::new();
                     let mut keyfields = Vec::new();
                     let mut mandatory_keys = Vec::new();
+                    let mut rest_field = None;
 
-                    let mut rel_index = 0;
+                    let mut keys = map_int_keys(attrs.starts_at, &attrs.skips, &fields).into_iter();
 
                     for field in fields.iter() {
                         let Field {
-                            index: field_index,
+                            index: _,
                             name: field_name,
                             attrs: field_attrs,
                         } = &field;
-                        let field_index = *field_index;
 
-                        loop {
-                            let abs_index = field_index as u64 + rel_index;
-                            if attrs.skips.iter().any(|v| *v == abs_index) {
-                                rel_index += 1;
-                            } else {
-                                break;
-                            }
+                        if field_attrs.rest {
+                            keydefs.push(quote! {
+                                let mut #field_name = ::std::collections::BTreeMap::new();
+                            });
+                            rest_field = Some(field_name);
+                            continue;
                         }
-                        let abs_index = field_index as u64 + rel_index;
+
+                        let key = keys.next().expect("key of mapint field");
                         let field_name_str = format!("{}", field_name);
+                        let decode = field_decode(field_attrs, &decode, quote! { v });
                         let keydef = quote! {
                             let mut #field_name = None;
                         };
                         let keyfield = quote! {
-                            #abs_index => {
-                                #field_name = Some(v.decode().map_err(|e| e.push_str(#field_name_str).push::<Self>())?);
+                            #key => {
+                                if #field_name.is_some() {
+                                    return Err(cbored::DecodeErrorKind::Custom(format!("duplicated key {}", key)).context::<Self>());
+                                }
+                                #field_name = Some(#decode.map_err(|e| e.push_str(#field_name_str).push::<Self>())?);
                             }
                         };
                         keydefs.push(keydef);
@@ -501,22 +690,121 @@ pub(crate) fn derive_struct_de(
                         }
                     }
 
+                    // iterate by index, decoding each key and value with a nested reader
+                    let map_loop = quote! {
+                        for (k, v) in (0..map.len()).map(|i| map[i])
+                    };
+
+                    // unknown keys are collected in the rest field if any, or rejected otherwise
+                    let unknown_key = match rest_field {
+                        Some(rest) => {
+                            let rest_str = format!("{}", rest);
+                            quote! {
+                                _ => {
+                                    let value = v.decode::<::cbored::CborData>().map_err(|e| e.push_str(#rest_str).push::<Self>())?;
+                                    let rest_key = ::std::convert::TryFrom::try_from(key).map_err(|_| {
+                                        cbored::DecodeErrorKind::Custom(format!("unknown key {}", key)).context::<Self>()
+                                    })?;
+                                    if #rest.insert(rest_key, value).is_some() {
+                                        return Err(cbored::DecodeErrorKind::Custom(format!("duplicated key {}", key)).context::<Self>());
+                                    }
+                                }
+                            }
+                        }
+                        None => quote! {
+                            _ => {
+                                return Err(cbored::DecodeErrorKind::Custom(format!(
+                                        "unknown key {}",
+                                        key
+                                    )).context::<Self>());
+                            }
+                        },
+                    };
+
                     quote! {
                         #prelude_sty_de
 
                         #( #keydefs )*
 
-                        let mut found_keys = 0;
-                        for (mut k, mut v) in map.iter() {
-                            let key: u64 = k.decode().map_err(|e| e.push::<Self>())?;
+                        #map_loop {
+                            let mut k = reader.nested_reader(k);
+                            let mut v = reader.nested_reader(v);
+                            let key: ::cbored::Scalar = k.decode().map_err(|e| e.push::<Self>())?;
+                            let key: i64 = match key.to_i64() {
+                                Some(key) => key,
+                                None => {
+                                    return Err(cbored::DecodeErrorKind::Custom(format!("unknown key {:?}", key)).context::<Self>());
+                                }
+                            };
 
-                            if (found_keys & (1 << key)) != 0 {
-                                return Err(cbored::DecodeErrorKind::Custom(format!("duplicated key {}", key)).context::<Self>());
-                            } else {
-                                found_keys |= 1 << key;
+                            match key {
+                                #( #keyfields )*
+                                // handle unknown keys
+                                #unknown_key
                             }
+                        }
 
-                            match key {
+                        #( #mandatory_keys )*
+
+                        Ok(#name { #(#field_names),*})
+                    }
+                }
+                DeStructure::MapText => {
+                    let mut keydefs = Vec::new();
+                    let mut keyfields = Vec::new();
+                    let mut mandatory_keys = Vec::new();
+
+                    for field in fields.iter() {
+                        let Field {
+                            index: _,
+                            name: field_name,
+                            attrs: field_attrs,
+                        } = &field;
+                        let key = map_text_key(field);
+                        let field_name_str = format!("{}", field_name);
+                        let decode = field_decode(field_attrs, &decode, quote! { v });
+                        keydefs.push(quote! {
+                            let mut #field_name = None;
+                        });
+                        keyfields.push(quote! {
+                            #key => {
+                                if #field_name.is_some() {
+                                    return Err(cbored::DecodeErrorKind::Custom(format!("duplicated key {}", key)).context::<Self>());
+                                }
+                                #field_name = Some(#decode.map_err(|e| e.push_str(#field_name_str).push::<Self>())?);
+                            }
+                        });
+
+                        if field_attrs.mandatory_map {
+                            mandatory_keys.push(quote! {
+                                let #field_name = match #field_name {
+                                    None => {
+                                        return Err(cbored::DecodeErrorKind::Custom(format!("missing {}", #key)).context::<Self>());
+                                    }
+                                    Some(value) => {
+                                        value
+                                    }
+                                };
+                            });
+                        }
+                    }
+
+                    // iterate by index, decoding each key and value with a nested reader
+                    let map_loop = quote! {
+                        for (k, v) in (0..map.len()).map(|i| map[i])
+                    };
+
+                    quote! {
+                        #prelude_sty_de
+
+                        #( #keydefs )*
+
+                        #map_loop {
+                            let mut k = reader.nested_reader(k);
+                            let mut v = reader.nested_reader(v);
+                            let key: String = k.decode().map_err(|e| e.push::<Self>())?;
+
+                            match key.as_str() {
                                 #( #keyfields )*
                                 // handle unknown keys
                                 _ => {
@@ -539,11 +827,12 @@ pub(crate) fn derive_struct_de(
                         let Field {
                             index: _,
                             name: field_name,
-                            attrs: _,
+                            attrs: field_attrs,
                         } = &field;
                         let field_name_str = format!("{}", field_name);
+                        let decode = field_decode(field_attrs, &decode, quote! { reader });
                         let de_body = quote! {
-                            let #field_name = reader.decode().map_err(|e| e.push_str(#field_name_str))?;
+                            let #field_name = #decode.map_err(|e| e.push_str(#field_name_str))?;
                         };
                         de_bodies.push(de_body);
                     }
@@ -563,20 +852,30 @@ pub(crate) fn derive_struct_de(
                 let Field {
                     index: field_index,
                     name: field_name,
-                    attrs: _,
+                    attrs: field_attrs,
                 } = &field;
                 let field_index = *field_index;
                 let field_name_str = format!("{}", field_name);
                 let de_body = match structure {
-                    DeStructure::Array { last_optional: _ } => quote! {
-                        let #field_name = array[#field_index].decode().map_err(|e| e.push_str(#field_name_str))?;
-                    },
-                    DeStructure::MapInt => {
+                    DeStructure::Array { last_optional: _ } => {
+                        let decode = field_decode(
+                            field_attrs,
+                            &decode,
+                            quote! { reader.nested_reader(array[#field_index]) },
+                        );
+                        quote! {
+                            let #field_name = #decode.map_err(|e| e.push_str(#field_name_str))?;
+                        }
+                    }
+                    DeStructure::MapInt | DeStructure::MapText => {
                         todo!()
                     }
-                    DeStructure::Flat => quote! {
-                        let #field_name = reader.decode().map_err(|e| e.push_str(#field_name_str))?;
-                    },
+                    DeStructure::Flat => {
+                        let decode = field_decode(field_attrs, &decode, quote! { reader });
+                        quote! {
+                            let #field_name = #decode.map_err(|e| e.push_str(#field_name_str))?;
+                        }
+                    }
                 };
                 de_bodies.push(de_body);
             }
@@ -589,12 +888,60 @@ pub(crate) fn derive_struct_de(
                 Ok(#name ( #(#indexes),* ))
             }
         }
-    };
+    }
+}
 
-    token_impl_deserializer(&name, de_body)
+// the key of each mapint field except the `rest` one, given with `key` or from its position
+// relative to `starts_at` jumping over the `skipkey` numbers
+//
+// panics if a key is used by another field or skipped with `skipkey`
+pub(crate) fn map_int_keys(starts_at: usize, skips: &[u64], fields: &[Field]) -> Vec<i64> {
+    let mut keys = Vec::new();
+    let mut rel_index = starts_at as u64;
+    for field in fields.iter().filter(|f| !f.attrs.rest) {
+        while skips.contains(&(field.index as u64 + rel_index)) {
+            rel_index += 1;
+        }
+        let key = field
+            .attrs
+            .key
+            .unwrap_or((field.index as u64 + rel_index) as i64);
+        if skips.iter().any(|skip| *skip as i64 == key) {
+            panic!("mapint key {} of field {} is skipped", key, field.name)
+        }
+        if keys.contains(&key) {
+            panic!(
+                "mapint key {} of field {} is used by another field",
+                key, field.name
+            )
+        }
+        keys.push(key);
+    }
+    keys
 }
 
-pub(crate) fn derive_struct(name: Ident, attrs: &[&Meta], st: DataStruct) -> TokenStream {
+// check that the text key of each maptext field, from its name or `rename`,
+// is not used by another field
+fn check_map_text_keys(fields: &[Field]) {
+    let mut keys = Vec::new();
+    for field in fields.iter() {
+        let key = map_text_key(field);
+        if keys.contains(&key) {
+            panic!(
+                "maptext key {} of field {} is used by another field",
+                key, field.name
+            )
+        }
+        keys.push(key);
+    }
+}
+
+pub(crate) fn derive_struct(
+    name: Ident,
+    generics: &Generics,
+    attrs: &[&Meta],
+    st: DataStruct,
+) -> TokenStream {
     let attrs = attrs
         .iter()
         .map(|meta| parse_attr(meta))
@@ -602,7 +949,54 @@ pub(crate) fn derive_struct(name: Ident, attrs: &[&Meta], st: DataStruct) -> Tok
             y.iter().fold(acc, |x, y| x.merge(y))
         });
 
-    let se = derive_struct_se(&name, &attrs, &st);
-    let de = derive_struct_de(&name, &attrs, &st);
+    if !generics.params.is_empty() && !attrs.borrowed {
+        panic!("cannot handle types with generics")
+    }
+
+    if attrs.transparent && (attrs.structure_type != StructureType::Flat || st.fields.len() != 1) {
+        panic!("transparent structure need exactly one field and no other structure type")
+    }
+
+    let fields = match get_struct_naming(&st.fields) {
+        StructOutput::Named(fields) => fields,
+        StructOutput::Unnamed(fields) => fields,
+    };
+    if fields
+        .iter()
+        .any(|f| f.attrs.map_starts_at.is_some() || !f.attrs.skips.is_empty())
+    {
+        panic!("map_starts_at and skipkey are not field attributes")
+    }
+    if fields.iter().any(|f| f.attrs.key.is_some()) && attrs.structure_type != StructureType::MapInt
+    {
+        panic!("key field attribute only supported with mapint structure")
+    }
+    if let Some(rest) = fields.iter().position(|f| f.attrs.rest) {
+        if attrs.structure_type != StructureType::MapInt || !attrs.accept.is_empty() {
+            panic!("rest field only supported with mapint structure")
+        }
+        if rest != fields.len() - 1 {
+            panic!("rest field need to be the last field")
+        }
+    }
+    if attrs.structure_type == StructureType::MapInt
+        || attrs.accept.contains(&StructureType::MapInt)
+    {
+        map_int_keys(attrs.starts_at, &attrs.skips, &fields);
+    }
+    if attrs.structure_type == StructureType::MapText
+        || attrs.accept.contains(&StructureType::MapText)
+    {
+        check_map_text_keys(&fields);
+    }
+
+    if attrs.structure_type == StructureType::MapText
+        && (!attrs.skips.is_empty() || attrs.starts_at != 0)
+    {
+        panic!("maptext structure does not support skipkey or map_starts_at")
+    }
+
+    let se = derive_struct_se(&name, generics, &attrs, &st);
+    let de = derive_struct_de(&name, generics, &attrs, &st);
     TokenStream::from(quote! { #se #de })
 }
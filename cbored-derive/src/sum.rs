@@ -1,56 +1,100 @@
 use proc_macro::TokenStream;
 
 use quote::quote;
-use syn::{DataEnum, Ident, Meta, Variant};
+use syn::{DataEnum, Generics, Ident, Meta, Variant};
 
 use super::attr::*;
 use super::common::*;
+use super::product::{
+    field_attrs, field_decode, field_encode, map_int_key_encode, map_int_keys, Field,
+};
 
 pub(crate) struct EnumAttrs {
     enumtype: EnumType,
     variant_starts_at: usize,
+    skips: Vec<u64>,
+    // whether the named fields of variants are serialized as an integer keyed map
+    mapint: bool,
 }
 
 impl EnumAttrs {
     pub fn from_metas(attrs: &[&Meta]) -> Self {
         let mut enumtype = EnumType::TagVariant;
         let mut variant_starts_at = 0;
+        let mut skips = Vec::new();
+        let mut mapint = false;
 
         for attr in attrs {
             for attr in parse_attr(&attr) {
                 match attr {
+                    Attr::Structure(StructureType::MapInt) => mapint = true,
                     Attr::Tag(_) | Attr::Structure(_) => {
                         panic!("enum does not support struct type attribute")
                     }
-                    Attr::SkipKey(_) => {
-                        panic!("enum does not support skip key attribute")
-                    }
+                    Attr::SkipKey(skip) => skips.push(skip),
                     Attr::MapStartsAt(_) => {
                         panic!("enum does not support map_starts_at key attribute")
                     }
+                    Attr::Borrowed => {
+                        panic!("enum does not support borrowed attribute")
+                    }
+                    Attr::Accept(_) => {
+                        panic!("enum does not support accept attribute")
+                    }
+                    Attr::Transparent => {
+                        panic!("enum does not support transparent attribute")
+                    }
                     Attr::EnumType(ty) => enumtype = ty,
                     Attr::VariantStartsAt(v) => variant_starts_at = v,
                 }
             }
         }
+        if mapint
+            && enumtype != EnumType::TagVariant
+            && enumtype != EnumType::StrVariant
+            && enumtype != EnumType::Compact
+        {
+            panic!("enum only support mapint structure with tagvariant, strvariant or compact")
+        }
         Self {
             enumtype,
             variant_starts_at,
+            skips,
+            mapint,
+        }
+    }
+}
+
+// associate each variant with its CBOR variant number, starting at `variant_starts_at`
+// and jumping over any number listed with `skipkey`
+fn enumerate_variant_indices<'a>(attrs: &EnumAttrs, st: &'a DataEnum) -> Vec<(u64, &'a Variant)> {
+    let mut variant_number = attrs.variant_starts_at as u64;
+    let mut output = Vec::with_capacity(st.variants.len());
+    for variant in st.variants.iter() {
+        while attrs.skips.contains(&variant_number) {
+            variant_number += 1;
         }
+        output.push((variant_number, variant));
+        variant_number += 1;
     }
+    output
 }
 
-#[derive(PartialEq, Eq)]
 pub struct VariantDef {
     cbor_type: Option<FieldCborType>,
     ty: VariantType,
+    // whether the last field is an Option, that can be omitted from the tagvariant array
+    last_optional: bool,
+    // the text discriminant of the variant for strvariant and strenum
+    str_name: String,
 }
 
-#[derive(PartialEq, Eq)]
 pub enum VariantType {
     NoParams,
     AnonParams { field_names: Vec<(usize, Ident)> },
     StructParams { field_names: Vec<Ident> },
+    // named fields serialized as an integer keyed map, along with the key of each field
+    MapParams { fields: Vec<Field>, keys: Vec<i64> },
 }
 
 // get whether the variant is of the form `A { a: ... , b: ... }` or `A(... , ...)` or `A`
@@ -63,26 +107,70 @@ fn variant_field(attrs: &EnumAttrs, variant: &Variant) -> VariantDef {
         panic!("fields should be all named or unnamed");
     }
 
-    let variant_attrs = get_my_attributes(&variant.attrs)
-        .map(|a| parse_field_attr(a))
-        .fold(FieldAttrs::default(), |acc, y| {
-            y.iter().fold(acc, |acc, y| acc.merge(y))
-        });
+    let variant_attrs = field_attrs(&variant.attrs);
+    if variant_attrs.rename.is_some()
+        && attrs.enumtype != EnumType::StrVariant
+        && attrs.enumtype != EnumType::StrEnum
+    {
+        panic!("rename is only supported on strvariant and strenum variants")
+    }
 
     match attrs.enumtype {
-        EnumType::EnumInt => assert_eq!(nb_items, 0),
+        EnumType::EnumInt | EnumType::StrEnum => assert_eq!(nb_items, 0),
         EnumType::EnumType => {
             if variant_attrs.cbor_type.is_none() {
                 panic!("enum type needs cbor-repr cbor-type attributes")
             }
         }
-        EnumType::TagVariant => {}
+        EnumType::TagVariant | EnumType::StrVariant | EnumType::Compact => {}
     };
 
     let cbor_type = variant_attrs.cbor_type;
 
+    let map_params = attrs.mapint && nb_items > 0 && all_named;
+    if !map_params && (variant_attrs.map_starts_at.is_some() || !variant_attrs.skips.is_empty()) {
+        panic!("map_starts_at and skipkey are only supported on mapint variants")
+    }
+
+    let last_optional = (attrs.enumtype == EnumType::TagVariant
+        || attrs.enumtype == EnumType::StrVariant
+        || attrs.enumtype == EnumType::Compact)
+        && !map_params
+        && variant
+            .fields
+            .iter()
+            .last()
+            .map(|f| is_option_type(&f.ty))
+            .unwrap_or(false);
+
     let ty = if nb_items == 0 {
         VariantType::NoParams
+    } else if map_params {
+        // the fields follow the mapint structure convention: optional unless `mandatory`
+        let fields = variant
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, f)| Field {
+                index,
+                name: f.ident.clone().unwrap(),
+                attrs: field_attrs(&f.attrs),
+            })
+            .collect::<Vec<_>>();
+        if fields.iter().any(|f| {
+            f.attrs.rest
+                || f.attrs.rename.is_some()
+                || f.attrs.map_starts_at.is_some()
+                || !f.attrs.skips.is_empty()
+        }) {
+            panic!("rest, rename, map_starts_at and skipkey are not variant field attributes")
+        }
+        let keys = map_int_keys(
+            variant_attrs.map_starts_at.unwrap_or(0),
+            &variant_attrs.skips,
+            &fields,
+        );
+        VariantType::MapParams { fields, keys }
     } else if all_named {
         let field_names = variant
             .fields
@@ -104,7 +192,41 @@ fn variant_field(attrs: &EnumAttrs, variant: &Variant) -> VariantDef {
     } else {
         panic!("internal error")
     };
-    VariantDef { ty, cbor_type }
+    let str_name = variant_attrs
+        .rename
+        .unwrap_or_else(|| format!("{}", variant.ident));
+    VariantDef {
+        ty,
+        cbor_type,
+        last_optional,
+        str_name,
+    }
+}
+
+// associate each variant with its CBOR discriminant, either its variant number
+// or its text name for strvariant and strenum
+fn enumerate_variant_discriminants<'a>(
+    attrs: &EnumAttrs,
+    st: &'a DataEnum,
+) -> Vec<(proc_macro2::TokenStream, &'a Variant)> {
+    let mut names = Vec::new();
+    enumerate_variant_indices(attrs, st)
+        .into_iter()
+        .map(|(variant_number, variant)| {
+            let discriminant =
+                if attrs.enumtype == EnumType::StrVariant || attrs.enumtype == EnumType::StrEnum {
+                    let name = variant_field(attrs, variant).str_name;
+                    if names.contains(&name) {
+                        panic!("duplicated variant name {}", name)
+                    }
+                    names.push(name.clone());
+                    quote! { #name }
+                } else {
+                    quote! { #variant_number }
+                };
+            (discriminant, variant)
+        })
+        .collect()
 }
 
 pub(crate) fn derive_enum_se(
@@ -149,6 +271,9 @@ pub(crate) fn derive_enum_se(
                             quote! { #field_name.encode(writer); },
                         )
                     }
+                    VariantType::MapParams { .. } => {
+                        panic!("cannot have enumtype with mapint structure")
+                    }
                     VariantType::NoParams => match variant_def.cbor_type {
                         None => panic!("cannot have no cbor_type"),
                         Some(FieldCborType::Null) => (quote! {}, quote! { writer.null(); }),
@@ -166,7 +291,7 @@ pub(crate) fn derive_enum_se(
             se_branches.push(se_branch);
         }
     } else {
-        for (variant_index, variant) in st.variants.iter().enumerate() {
+        for (variant_number, variant) in enumerate_variant_discriminants(&attrs, st) {
             let ident = &variant.ident;
 
             let nb_items = variant.fields.len();
@@ -174,56 +299,78 @@ pub(crate) fn derive_enum_se(
             let variant_def = variant_field(&attrs, &variant);
             let variant_type = &variant_def.ty;
 
-            let variant_number = attrs.variant_starts_at + variant_index;
-
-            let (parameters, se_fields) = {
+            let (parameters, field_idents) = {
                 match &variant_type {
                     VariantType::StructParams { field_names } => {
-                        let de_field_names = field_names
-                            .iter()
-                            .map(|ident| quote! { #ident })
-                            .collect::<Vec<_>>();
-                        let se_fields = field_names
+                        let parameters = quote! { { #( #field_names ),* } };
+                        (parameters, field_names.clone())
+                    }
+                    VariantType::MapParams { fields, .. } => {
+                        let field_idents = fields
                             .iter()
-                            .map(|ident| {
-                                quote! { writer.encode(#ident); }
-                            })
+                            .map(|field| field.name.clone())
                             .collect::<Vec<_>>();
-                        let parameters = quote! { { #( #de_field_names ),* } };
-                        (parameters, se_fields)
+                        let parameters = quote! { { #( #field_idents ),* } };
+                        (parameters, field_idents)
                     }
                     VariantType::AnonParams { field_names } => {
-                        let de_field_names = field_names
-                            .iter()
-                            .map(|(_, ident)| quote! { #ident })
-                            .collect::<Vec<_>>();
-                        let se_fields = field_names
+                        let field_idents = field_names
                             .iter()
-                            .map(|(_, ident)| quote! { writer.encode(#ident); })
+                            .map(|(_, ident)| ident.clone())
                             .collect::<Vec<_>>();
-                        let parameters = quote! { ( #( #de_field_names ),* ) };
-                        (parameters, se_fields)
+                        let parameters = quote! { ( #( #field_idents ),* ) };
+                        (parameters, field_idents)
                     }
                     VariantType::NoParams => (quote! {}, vec![]),
                 }
             };
 
-            // skip writing array in a case of enumint mode and no params
-            let se_branch_body =
-                if variant_type == &VariantType::NoParams && attrs.enumtype == EnumType::EnumInt {
-                    quote! {
-                        writer.encode(&(#variant_number as u64));
-                        #(#se_fields)*
-                    }
-                } else {
-                    quote! {
-                        let len = ::cbored::StructureLength::from(1 + #nb_items as u64);
-                        writer.array_build(len, |writer| {
-                            writer.encode(&(#variant_number as u64));
-                            #(#se_fields)*
-                        })
+            // the last optional field is only written when present
+            let se_fields = field_idents
+                .iter()
+                .enumerate()
+                .map(|(fidx, ident)| {
+                    if variant_def.last_optional && fidx == nb_items - 1 {
+                        quote! {
+                            match #ident {
+                                None => (),
+                                Some(v) => writer.encode(v),
+                            };
+                        }
+                    } else {
+                        quote! { writer.encode(#ident); }
                     }
-                };
+                })
+                .collect::<Vec<_>>();
+
+            let nb_actual_items = match field_idents.last() {
+                Some(last) if variant_def.last_optional => quote! {
+                    (#nb_items as u64 - 1) + match #last { None => 0, Some(_) => 1 }
+                },
+                _ => quote! { #nb_items as u64 },
+            };
+
+            // skip writing array in a case of enumint, strenum or compact mode and no params
+            let se_branch_body = if let VariantType::MapParams { fields, keys } = variant_type {
+                se_variant_map(&variant_number, fields, keys)
+            } else if matches!(variant_type, VariantType::NoParams)
+                && (attrs.enumtype == EnumType::EnumInt
+                    || attrs.enumtype == EnumType::StrEnum
+                    || attrs.enumtype == EnumType::Compact)
+            {
+                quote! {
+                    writer.encode(&#variant_number);
+                    #(#se_fields)*
+                }
+            } else {
+                quote! {
+                    let len = ::cbored::StructureLength::from(1 + #nb_actual_items);
+                    writer.array_build(len, |writer| {
+                        writer.encode(&#variant_number);
+                        #(#se_fields)*
+                    })
+                }
+            };
             let se_branch = quote! {
                 Self::#ident #parameters => { #se_branch_body }
             };
@@ -237,7 +384,136 @@ pub(crate) fn derive_enum_se(
             #( #se_branches )*
         }
     };
-    token_impl_serializer(&name, se_body)
+    token_impl_serializer(&name, &Generics::default(), se_body)
+}
+
+// serialize the named fields of a variant as an integer keyed map following the discriminant,
+// like a mapint structure where the fields not marked `mandatory` are omitted when None
+//
+// serialized as : ARRAY(2) [ discriminant, MAP { UINT(0) => field0, UINT(1) => field1, .. } ]
+fn se_variant_map(
+    discriminant: &proc_macro2::TokenStream,
+    fields: &[Field],
+    keys: &[i64],
+) -> proc_macro2::TokenStream {
+    let mut fixed = 0u64;
+    let mut len_for_optionals = Vec::new();
+    let mut fields_write_map = Vec::new();
+    for (field, key) in fields.iter().zip(keys) {
+        let ident = &field.name;
+        let write_key = map_int_key_encode(*key);
+        if field.attrs.mandatory_map {
+            let encode = field_encode(&field.attrs, quote! { #ident });
+            fields_write_map.push(quote! {
+                #write_key
+                #encode;
+            });
+            fixed += 1;
+        } else {
+            let encode = field_encode(&field.attrs, quote! { value });
+            fields_write_map.push(quote! {
+                match #ident {
+                    None => {}
+                    Some(value) => {
+                        #write_key
+                        #encode;
+                    }
+                }
+            });
+            len_for_optionals.push(quote! {
+                + match #ident {
+                    None => 0,
+                    Some(_) => 1,
+                }
+            });
+        }
+    }
+    quote! {
+        writer.array_build(::cbored::StructureLength::from(2), |writer| {
+            writer.encode(&#discriminant);
+            let nb_values: u64 = #fixed #( #len_for_optionals )*;
+            writer.map_build(::cbored::StructureLength::from(nb_values), |writer| {
+                #( #fields_write_map )*
+            })
+        })
+    }
+}
+
+// deserialize the named fields of a variant from the integer keyed map following the discriminant
+fn de_variant_map(
+    name_type: &str,
+    ident: &Ident,
+    variant_name: &str,
+    fields: &[Field],
+    keys: &[i64],
+) -> proc_macro2::TokenStream {
+    let mut keydefs = Vec::new();
+    let mut keyfields = Vec::new();
+    let mut mandatory_keys = Vec::new();
+    for (field, key) in fields.iter().zip(keys) {
+        let ident = &field.name;
+        let fname_str = format!("{}", ident);
+        let decode = field_decode(&field.attrs, &quote! { decode }, quote! { v });
+        keydefs.push(quote! {
+            let mut #ident = None;
+        });
+        keyfields.push(quote! {
+            #key => {
+                if #ident.is_some() {
+                    return Err(::cbored::DecodeErrorKind::Custom(format!("duplicated key {}", key)).context::<Self>());
+                }
+                #ident = Some(#decode.map_err(|e| e.push_str(#fname_str).push_str(#variant_name).push::<Self>())?);
+            }
+        });
+        if field.attrs.mandatory_map {
+            mandatory_keys.push(quote! {
+                let #ident = match #ident {
+                    None => {
+                        return Err(::cbored::DecodeErrorKind::Custom(format!("missing {}", #fname_str)).context::<Self>());
+                    }
+                    Some(value) => value,
+                };
+            });
+        }
+    }
+    let field_idents = fields.iter().map(|field| &field.name);
+    quote! {
+        if array.len() != 2 {
+            return Err(::cbored::DecodeErrorKind::Custom(
+                format!("wrong number of items for {}::{} got {} expected 2",
+                    #name_type,
+                    #variant_name,
+                    array.len())
+                ).context::<Self>()
+            );
+        }
+        let mut map_reader = reader.nested_reader(array[1]);
+        let map = map_reader.map().map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.context_str(#variant_name).push::<Self>())?;
+
+        #( #keydefs )*
+
+        for (k, v) in (0..map.len()).map(|i| map[i]) {
+            let mut k = map_reader.nested_reader(k);
+            let mut v = map_reader.nested_reader(v);
+            let key: ::cbored::Scalar = k.decode().map_err(|e| e.push_str(#variant_name).push::<Self>())?;
+            let key: i64 = match key.to_i64() {
+                Some(key) => key,
+                None => {
+                    return Err(::cbored::DecodeErrorKind::Custom(format!("unknown key {:?}", key)).context::<Self>());
+                }
+            };
+            match key {
+                #( #keyfields )*
+                _ => {
+                    return Err(::cbored::DecodeErrorKind::Custom(format!("unknown key {}", key)).context::<Self>());
+                }
+            }
+        }
+
+        #( #mandatory_keys )*
+
+        Ok(Self::#ident { #( #field_idents ),* })
+    }
 }
 
 pub(crate) fn derive_enum_de(
@@ -250,17 +526,18 @@ pub(crate) fn derive_enum_de(
     let attrs = EnumAttrs::from_metas(attrs);
 
     let mut field_matches = Vec::new();
+    // for compact, the variants without fields are decoded from a bare integer
+    let mut unit_matches = Vec::new();
 
     match attrs.enumtype {
-        EnumType::EnumInt => {
+        EnumType::EnumInt | EnumType::StrEnum => {
             // each branch of deserialization is of the form
             //     X => {
             //          get field 0..n;
             //          Ok(Constructor field 0..n)
             //     }
-            for (variant_index, variant) in st.variants.iter().enumerate() {
+            for (variant_number, variant) in enumerate_variant_discriminants(&attrs, st) {
                 let ident = &variant.ident;
-                let variant_number = attrs.variant_starts_at + variant_index;
 
                 let de_branch = quote! {
                     #variant_number => {
@@ -304,6 +581,9 @@ pub(crate) fn derive_enum_de(
                 };
 
                 let (field_parameter, variant_field_deser) = match variant_type {
+                    VariantType::MapParams { .. } => {
+                        panic!("cannot have enumtype with mapint structure")
+                    }
                     VariantType::NoParams => {
                         if cbor_type != FieldCborType::Null {
                             panic!("no arguemnt cannot be anything else than cbor null")
@@ -354,72 +634,108 @@ pub(crate) fn derive_enum_de(
                 field_matches.push(variant_match);
             }
         }
-        EnumType::TagVariant => {
-            for (variant_index, variant) in st.variants.iter().enumerate() {
+        EnumType::TagVariant | EnumType::StrVariant | EnumType::Compact => {
+            for (variant_number, variant) in enumerate_variant_discriminants(&attrs, st) {
                 let ident = &variant.ident;
                 let variant_name = format!("{}", ident);
-                let variant_number = attrs.variant_starts_at + variant_index;
 
                 let nb_items = variant.fields.len();
 
                 let variant_def = variant_field(&attrs, &variant);
                 let variant_type = &variant_def.ty;
 
-                // skip array length check in a case of enumint mode
-                let de_array_lencheck = quote! {
-                    if array.len() != #nb_items + 1 {
-                        return Err(::cbored::DecodeErrorKind::Custom(
-                            format!("wrong number of items for {}::{} got {} expected {}",
-                                #name_type,
-                                #variant_name,
-                                array.len(),
-                                #nb_items + 1)
-                            ).context::<Self>()
-                        );
+                if attrs.enumtype == EnumType::Compact
+                    && matches!(variant_type, VariantType::NoParams)
+                {
+                    unit_matches.push(quote! {
+                        #variant_number => {
+                            Ok(Self::#ident)
+                        }
+                    });
+                    continue;
+                }
+
+                // with a last optional field, the array can be one item shorter
+                let de_array_lencheck = if variant_def.last_optional {
+                    quote! {
+                        if array.len() != #nb_items + 1 && array.len() != #nb_items {
+                            return Err(::cbored::DecodeErrorKind::Custom(
+                                format!("wrong number of items for {}::{} got {} expected {} or {}",
+                                    #name_type,
+                                    #variant_name,
+                                    array.len(),
+                                    #nb_items + 1,
+                                    #nb_items)
+                                ).context::<Self>()
+                            );
+                        }
+                    }
+                } else {
+                    quote! {
+                        if array.len() != #nb_items + 1 {
+                            return Err(::cbored::DecodeErrorKind::Custom(
+                                format!("wrong number of items for {}::{} got {} expected {}",
+                                    #name_type,
+                                    #variant_name,
+                                    array.len(),
+                                    #nb_items + 1)
+                                ).context::<Self>()
+                            );
+                        }
                     }
                 };
 
-                let (parameters, de_fields) = {
+                if let VariantType::MapParams { fields, keys } = variant_type {
+                    let de_branch_body =
+                        de_variant_map(&name_type, ident, &variant_name, fields, keys);
+                    field_matches.push(quote! {
+                        #variant_number => {
+                            #de_branch_body
+                        }
+                    });
+                    continue;
+                }
+
+                let (parameters, field_idents) = {
                     match variant_type {
                         VariantType::StructParams { field_names } => {
-                            let de_field_names = field_names
-                                .iter()
-                                .map(|ident| quote! { #ident })
-                                .collect::<Vec<_>>();
-                            let de_fields = de_field_names
-                                .iter()
-                                .enumerate()
-                                .map(|(fidx, fname)| {
-                                    let fname_str = format!("{}", fname);
-                                    quote! {
-                                        let #fname = array[#fidx + 1].decode().map_err(|e| e.push_str(#fname_str).push_str(#variant_name).push::<Self>())?;
-                                    }
-                                })
-                                .collect::<Vec<_>>();
-                            let parameters = quote! { { #( #de_field_names ),* } };
-                            (parameters, de_fields)
+                            let parameters = quote! { { #( #field_names ),* } };
+                            (parameters, field_names.clone())
                         }
+                        VariantType::MapParams { .. } => unreachable!(),
                         VariantType::AnonParams { field_names } => {
-                            let de_field_names = field_names
-                                .iter()
-                                .map(|(_, ident)| quote! { #ident })
-                                .collect::<Vec<_>>();
-                            let de_fields = field_names
+                            let field_idents = field_names
                                 .iter()
-                                .map(|(fidx, ident)| {
-                                    let fname_str = format!("{}", ident);
-                                    quote! {
-                                        let #ident = array[#fidx + 1].decode().map_err(|e| e.push_str(#fname_str).push_str(#variant_name).push::<Self>())?;
-                                    }
-                                })
+                                .map(|(_, ident)| ident.clone())
                                 .collect::<Vec<_>>();
-                            let parameters = quote! { ( #( #de_field_names ),* ) };
-                            (parameters, de_fields)
+                            let parameters = quote! { ( #( #field_idents ),* ) };
+                            (parameters, field_idents)
                         }
                         VariantType::NoParams => (quote! {}, vec![]),
                     }
                 };
 
+                let de_fields = field_idents
+                    .iter()
+                    .enumerate()
+                    .map(|(fidx, fname)| {
+                        let fname_str = format!("{}", fname);
+                        if variant_def.last_optional && fidx == nb_items - 1 {
+                            quote! {
+                                let #fname = if array.len() == #fidx + 2 {
+                                    Some(reader.decode_nested(array[#fidx + 1]).map_err(|e| e.push_str(#fname_str).push_str(#variant_name).push::<Self>())?)
+                                } else {
+                                    None
+                                };
+                            }
+                        } else {
+                            quote! {
+                                let #fname = reader.decode_nested(array[#fidx + 1]).map_err(|e| e.push_str(#fname_str).push_str(#variant_name).push::<Self>())?;
+                            }
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
                 // each branch of deserialization is of the form
                 //     X => {
                 //          check_len();
@@ -440,8 +756,7 @@ pub(crate) fn derive_enum_de(
 
     let body = match attrs.enumtype {
         EnumType::EnumInt => quote! {
-            let variant: u64 = reader.decode()?;
-            let variant: usize = variant as usize;
+            let variant: u64 = reader.decode().map_err(|e| e.push::<Self>())?;
             match variant {
                 #( #field_matches )*
                 _ => {
@@ -449,6 +764,15 @@ pub(crate) fn derive_enum_de(
                 }
             }
         },
+        EnumType::StrEnum => quote! {
+            let variant: String = reader.decode().map_err(|e| e.push::<Self>())?;
+            match variant.as_str() {
+                #( #field_matches )*
+                _ => {
+                    return Err(::cbored::DecodeErrorKind::Custom(format!("{} variant name {} is not known", #name_type, variant)).context::<Self>());
+                }
+            }
+        },
         EnumType::EnumType => {
             quote! {
                 let cbor_type = reader.peek_type().map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.context::<Self>())?;
@@ -469,8 +793,7 @@ pub(crate) fn derive_enum_de(
                     }
                     _ => {}
                 };
-                let variant: u64 = array[0].decode()?;
-                let variant: usize = variant as usize;
+                let variant: u64 = reader.decode_nested(array[0]).map_err(|e| e.push::<Self>())?;
                 match variant {
                     #( #field_matches )*
                     _ => {
@@ -479,6 +802,56 @@ pub(crate) fn derive_enum_de(
                 }
             }
         }
+        EnumType::Compact => {
+            quote! {
+                let cbor_type = reader.peek_type().map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.context::<Self>())?;
+                match cbor_type {
+                    ::cbored::Type::Array => {
+                        let array = reader.array().map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.context::<Self>())?;
+                        match array.len() {
+                            0 => {
+                                return Err(::cbored::DecodeErrorKind::Custom(format!("expecting at least 1 item in variant encoding of {}", #name_type)).context::<Self>());
+                            }
+                            _ => {}
+                        };
+                        let variant: u64 = reader.decode_nested(array[0]).map_err(|e| e.push::<Self>())?;
+                        match variant {
+                            #( #field_matches )*
+                            _ => {
+                                return Err(::cbored::DecodeErrorKind::Custom(format!("{} variant number {} is not known", #name_type, variant)).context::<Self>());
+                            }
+                        }
+                    }
+                    _ => {
+                        let variant: u64 = reader.decode().map_err(|e| e.push::<Self>())?;
+                        match variant {
+                            #( #unit_matches )*
+                            _ => {
+                                return Err(::cbored::DecodeErrorKind::Custom(format!("{} variant number {} is not known", #name_type, variant)).context::<Self>());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        EnumType::StrVariant => {
+            quote! {
+                let array = reader.array().map_err(::cbored::DecodeErrorKind::ReaderError).map_err(|e| e.context::<Self>())?;
+                match array.len() {
+                    0 => {
+                        return Err(::cbored::DecodeErrorKind::Custom(format!("expecting at least 1 item in variant encoding of {}", #name_type)).context::<Self>());
+                    }
+                    _ => {}
+                };
+                let variant: String = reader.decode_nested(array[0]).map_err(|e| e.push::<Self>())?;
+                match variant.as_str() {
+                    #( #field_matches )*
+                    _ => {
+                        return Err(::cbored::DecodeErrorKind::Custom(format!("{} variant name {} is not known", #name_type, variant)).context::<Self>());
+                    }
+                }
+            }
+        }
     };
 
     /*
@@ -523,7 +896,7 @@ pub(crate) fn derive_enum_de(
                             let fname_str = format!("{}", fname);
                             if use_array {
                                 quote! {
-                                    let #fname = array[#fidx + 1].decode().map_err(|e| e.push_str(#fname_str).push_str(#variant_name).push::<Self>())?;
+                                    let #fname = reader.decode_nested(array[#fidx + 1]).map_err(|e| e.push_str(#fname_str).push_str(#variant_name).push::<Self>())?;
                                 }
                             } else {
                                 quote! {
@@ -546,7 +919,7 @@ pub(crate) fn derive_enum_de(
                             let fname_str = format!("{}", ident);
                             if use_array {
                                 quote! {
-                                    let #ident = array[#fidx + 1].decode().map_err(|e| e.push_str(#fname_str).push_str(#variant_name).push::<Self>())?;
+                                    let #ident = reader.decode_nested(array[#fidx + 1]).map_err(|e| e.push_str(#fname_str).push_str(#variant_name).push::<Self>())?;
                                 }
                             } else {
                                 quote! {
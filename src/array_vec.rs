@@ -0,0 +1,81 @@
+//! Encode and Decode for `ArrayVec`, a bounded collection without heap allocation
+use super::decode::{Decode, DecodeError, DecodeErrorKind};
+use super::encode::Encode;
+use super::reader::Reader;
+use super::types::StructureLength;
+use super::writer::Writer;
+use arrayvec::ArrayVec;
+
+/// Encode the elements as a definite CBOR array
+impl<T: Encode, const N: usize> Encode for ArrayVec<T, N> {
+    fn encode(&self, writer: &mut Writer) {
+        writer.array_build(StructureLength::from(self.len() as u64), |writer| {
+            for e in self.iter() {
+                writer.encode(e)
+            }
+        })
+    }
+}
+
+/// Decode a CBOR array of up to N elements
+///
+/// An array with more than N elements fails with `DecodeErrorKind::UnexpectedLength`
+impl<T: Decode, const N: usize> Decode for ArrayVec<T, N> {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let array = reader
+            .array()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        if array.len() > N {
+            return Err(DecodeErrorKind::UnexpectedLength {
+                expected: N,
+                got: array.len(),
+            }
+            .context::<Self>());
+        }
+        let mut out = ArrayVec::new();
+        for (i, slice) in array.elements.iter().enumerate() {
+            let t = reader
+                .nested_reader(slice)
+                .decode()
+                .map_err(|e| e.push_string(format!("{}", i)).push::<Self>())?;
+            out.push(t);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_from_bytes, encode_to_bytes};
+
+    #[test]
+    fn bounded() {
+        let mut v = ArrayVec::<u64, 3>::new();
+        v.push(1);
+        v.push(2);
+        let bytes = encode_to_bytes(&v);
+        assert_eq!(bytes, [0x82, 0x01, 0x02]);
+        assert_eq!(decode_from_bytes::<ArrayVec<u64, 3>>(&bytes).unwrap(), v);
+        assert_eq!(
+            decode_from_bytes::<ArrayVec<u64, 3>>(&[0x9f, 0x01, 0x02, 0x03, 0xff])
+                .unwrap()
+                .as_slice(),
+            [1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn too_many_elements() {
+        let err =
+            decode_from_bytes::<ArrayVec<u64, 3>>(&[0x84, 0x01, 0x02, 0x03, 0x04]).unwrap_err();
+        assert!(matches!(
+            err.error(),
+            DecodeErrorKind::UnexpectedLength {
+                expected: 3,
+                got: 4
+            }
+        ));
+    }
+}
@@ -0,0 +1,375 @@
+//! COSE Key structure (RFC 9052 section 7)
+use super::decode::{Decode, DecodeError, DecodeErrorKind};
+use super::encode::Encode;
+use super::reader::Reader;
+use super::types::{StructureLength, Type, Value};
+use super::writer::Writer;
+use std::collections::BTreeMap;
+
+const LABEL_KTY: i64 = 1;
+const LABEL_KID: i64 = 2;
+const LABEL_ALG: i64 = 3;
+const LABEL_KEY_OPS: i64 = 4;
+const LABEL_BASE_IV: i64 = 5;
+
+/// COSE value that is either an integer or a text, e.g. a key type or an algorithm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Label {
+    Int(i64),
+    Text(String),
+}
+
+/// COSE Key, an integer keyed map of the common key parameters, and the
+/// key type specific parameters (e.g. -1 for the curve, -2 and -3 for the coordinates)
+///
+/// Text labels are not supported, and duplicated labels are rejected.
+/// The map is encoded with the labels sorted in the deterministic encoding order,
+/// which is the positive labels first then the negative labels
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoseKey {
+    kty: Label,
+    kid: Option<Vec<u8>>,
+    alg: Option<Label>,
+    key_ops: Option<Vec<Label>>,
+    base_iv: Option<Vec<u8>>,
+    params: BTreeMap<i64, Value>,
+}
+
+/// Builder of a COSE Key
+pub struct CoseKeyBuilder(CoseKey);
+
+impl CoseKey {
+    /// Key type (label 1)
+    pub fn kty(&self) -> &Label {
+        &self.kty
+    }
+
+    /// Key identifier (label 2)
+    pub fn kid(&self) -> Option<&[u8]> {
+        self.kid.as_deref()
+    }
+
+    /// Algorithm the key is restricted to (label 3)
+    pub fn alg(&self) -> Option<&Label> {
+        self.alg.as_ref()
+    }
+
+    /// Operations the key is restricted to (label 4)
+    pub fn key_ops(&self) -> Option<&[Label]> {
+        self.key_ops.as_deref()
+    }
+
+    /// Base IV to be xor-ed with the partial IVs (label 5)
+    pub fn base_iv(&self) -> Option<&[u8]> {
+        self.base_iv.as_deref()
+    }
+
+    /// Get any other parameter of the key by its label
+    pub fn param(&self, label: i64) -> Option<&Value> {
+        self.params.get(&label)
+    }
+
+    /// Curve of an OKP or EC2 key (label -1)
+    pub fn crv(&self) -> Option<i64> {
+        self.param(-1).and_then(|v| v.as_i64())
+    }
+
+    /// X coordinate of an OKP or EC2 key (label -2)
+    pub fn x(&self) -> Option<&[u8]> {
+        self.param(-2).and_then(|v| v.as_bytes())
+    }
+
+    /// Y coordinate of an EC2 key (label -3), when not given as a sign bit
+    pub fn y(&self) -> Option<&[u8]> {
+        self.param(-3).and_then(|v| v.as_bytes())
+    }
+
+    /// Private key of an OKP or EC2 key (label -4)
+    pub fn d(&self) -> Option<&[u8]> {
+        self.param(-4).and_then(|v| v.as_bytes())
+    }
+
+    /// Key value of a symmetric key (label -1)
+    pub fn k(&self) -> Option<&[u8]> {
+        self.param(-1).and_then(|v| v.as_bytes())
+    }
+}
+
+impl CoseKeyBuilder {
+    /// Create a new builder for a key of type `kty`
+    pub fn new(kty: Label) -> Self {
+        Self(CoseKey {
+            kty,
+            kid: None,
+            alg: None,
+            key_ops: None,
+            base_iv: None,
+            params: BTreeMap::new(),
+        })
+    }
+
+    pub fn kid(&mut self, kid: &[u8]) {
+        self.0.kid = Some(kid.to_vec())
+    }
+
+    pub fn alg(&mut self, alg: Label) {
+        self.0.alg = Some(alg)
+    }
+
+    pub fn key_ops(&mut self, key_ops: Vec<Label>) {
+        self.0.key_ops = Some(key_ops)
+    }
+
+    pub fn base_iv(&mut self, base_iv: &[u8]) {
+        self.0.base_iv = Some(base_iv.to_vec())
+    }
+
+    /// Set a key type specific parameter, replacing any previous value of this label
+    ///
+    /// Panics if the label is one of the common parameters from 1 to 5,
+    /// which have their own setters
+    pub fn param(&mut self, label: i64, value: Value) {
+        assert!(
+            !(LABEL_KTY..=LABEL_BASE_IV).contains(&label),
+            "label {} is a common parameter",
+            label
+        );
+        self.0.params.insert(label, value);
+    }
+
+    pub fn build(self) -> CoseKey {
+        self.0
+    }
+}
+
+impl Encode for Label {
+    fn encode(&self, writer: &mut Writer) {
+        match self {
+            Label::Int(v) => writer.encode(&Value::int(*v)),
+            Label::Text(s) => writer.encode(s),
+        }
+    }
+}
+
+impl Decode for Label {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let ty = reader
+            .peek_type()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        match ty {
+            Type::Text => Ok(Label::Text(reader.decode().map_err(|e| e.push::<Self>())?)),
+            _ => decode_int(reader).map(Label::Int),
+        }
+    }
+}
+
+// decode a positive or negative integer that fit in a i64
+fn decode_int(reader: &mut Reader) -> Result<i64, DecodeError> {
+    let scalar = reader
+        .scalar()
+        .map_err(DecodeErrorKind::ReaderError)
+        .map_err(|e| e.context::<i64>())?;
+    scalar.to_i64().ok_or_else(|| {
+        DecodeErrorKind::Custom(format!("integer {:?} out of the i64 range", scalar))
+            .context::<i64>()
+    })
+}
+
+impl Encode for CoseKey {
+    fn encode(&self, writer: &mut Writer) {
+        let mut entries = vec![(LABEL_KTY, encode_value(&self.kty))];
+        let common = [
+            (
+                LABEL_KID,
+                self.kid.as_ref().map(|v| encode_value(&Value::bytes(v))),
+            ),
+            (LABEL_ALG, self.alg.as_ref().map(encode_value)),
+            (
+                LABEL_KEY_OPS,
+                self.key_ops.as_ref().map(|v| encode_labels(v)),
+            ),
+            (
+                LABEL_BASE_IV,
+                self.base_iv
+                    .as_ref()
+                    .map(|v| encode_value(&Value::bytes(v))),
+            ),
+        ];
+        for (label, value) in common {
+            if let Some(value) = value {
+                entries.push((label, value));
+            }
+        }
+        for (label, value) in self.params.iter() {
+            entries.push((*label, encode_value(value)));
+        }
+
+        // sort in the bytewise order of the encoded labels
+        let mut entries = entries
+            .into_iter()
+            .map(|(label, value)| (encode_value(&Value::int(label)), value))
+            .collect::<Vec<_>>();
+        entries.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+        writer.map_build(StructureLength::from(entries.len() as u64), |writer| {
+            for (k, v) in entries.iter() {
+                writer.append_slice(k);
+                writer.append_slice(v);
+            }
+        })
+    }
+}
+
+fn encode_value<T: Encode + ?Sized>(t: &T) -> Vec<u8> {
+    let mut writer = Writer::new();
+    writer.encode(t);
+    writer.finalize()
+}
+
+fn encode_labels(labels: &[Label]) -> Vec<u8> {
+    let mut writer = Writer::new();
+    writer.array_build(StructureLength::from(labels.len() as u64), |writer| {
+        for label in labels {
+            writer.encode(label);
+        }
+    });
+    writer.finalize()
+}
+
+impl Decode for CoseKey {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let map = reader
+            .map()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        let mut kty = None;
+        let mut kid = None;
+        let mut alg = None;
+        let mut key_ops = None;
+        let mut base_iv = None;
+        let mut params = BTreeMap::new();
+        for (i, (k, v)) in map.elements.iter().enumerate() {
+            let mut k = reader.nested_reader(k);
+            let mut v = reader.nested_reader(v);
+            let label = decode_int(&mut k)
+                .map_err(|e| e.push_string(format!("key {}", i)).push::<Self>())?;
+            let duplicated = match label {
+                LABEL_KTY => kty
+                    .replace(v.decode().map_err(|e| e.push_str("kty"))?)
+                    .is_some(),
+                LABEL_KID => kid
+                    .replace(v.decode().map_err(|e| e.push_str("kid"))?)
+                    .is_some(),
+                LABEL_ALG => alg
+                    .replace(v.decode().map_err(|e| e.push_str("alg"))?)
+                    .is_some(),
+                LABEL_KEY_OPS => key_ops
+                    .replace(decode_labels(&mut v).map_err(|e| e.push_str("key_ops"))?)
+                    .is_some(),
+                LABEL_BASE_IV => base_iv
+                    .replace(v.decode().map_err(|e| e.push_str("base_iv"))?)
+                    .is_some(),
+                _ => params
+                    .insert(
+                        label,
+                        v.decode()
+                            .map_err(|e| e.push_string(format!("label {}", label)))?,
+                    )
+                    .is_some(),
+            };
+            if duplicated {
+                return Err(
+                    DecodeErrorKind::Custom(format!("duplicated label {}", label))
+                        .context::<Self>(),
+                );
+            }
+        }
+        let kty = kty
+            .ok_or_else(|| DecodeErrorKind::Custom("missing kty".to_string()).context::<Self>())?;
+        Ok(CoseKey {
+            kty,
+            kid,
+            alg,
+            key_ops,
+            base_iv,
+            params,
+        })
+    }
+}
+
+fn decode_labels(reader: &mut Reader) -> Result<Vec<Label>, DecodeError> {
+    let array = reader
+        .array()
+        .map_err(DecodeErrorKind::ReaderError)
+        .map_err(|e| e.context::<Vec<Label>>())?;
+    array
+        .elements
+        .iter()
+        .enumerate()
+        .map(|(i, slice)| {
+            reader
+                .nested_reader(slice)
+                .decode()
+                .map_err(|e| e.push_string(format!("{}", i)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_from_bytes, encode_to_bytes};
+
+    #[test]
+    fn cose_key_ec2() {
+        let mut builder = CoseKeyBuilder::new(Label::Int(2));
+        builder.param(-2, Value::bytes(&[0xaa]));
+        builder.param(-1, Value::int(1));
+        builder.kid(b"k");
+        builder.param(-3, Value::bytes(&[0xbb]));
+        let key = builder.build();
+
+        // {1: 2, 2: h'6b', -1: 1, -2: h'aa', -3: h'bb'}
+        let bytes = [
+            0xa5, 0x01, 0x02, 0x02, 0x41, 0x6b, 0x20, 0x01, 0x21, 0x41, 0xaa, 0x22, 0x41, 0xbb,
+        ];
+        assert_eq!(encode_to_bytes(&key), bytes);
+        let decoded = decode_from_bytes::<CoseKey>(&bytes).unwrap();
+        assert_eq!(decoded, key);
+        assert_eq!(decoded.kty(), &Label::Int(2));
+        assert_eq!(decoded.kid(), Some(&b"k"[..]));
+        assert_eq!(decoded.alg(), None);
+        assert_eq!(decoded.crv(), Some(1));
+        assert_eq!(decoded.x(), Some(&[0xaa][..]));
+        assert_eq!(decoded.y(), Some(&[0xbb][..]));
+        assert_eq!(decoded.d(), None);
+    }
+
+    #[test]
+    fn cose_key_symmetric() {
+        // {1: 4, 3: -7, 4: [1, "x"], -1: h'00'}
+        let bytes = [
+            0xa4, 0x01, 0x04, 0x03, 0x26, 0x04, 0x82, 0x01, 0x61, b'x', 0x20, 0x41, 0x00,
+        ];
+        let key = decode_from_bytes::<CoseKey>(&bytes).unwrap();
+        assert_eq!(key.alg(), Some(&Label::Int(-7)));
+        assert_eq!(
+            key.key_ops(),
+            Some(&[Label::Int(1), Label::Text("x".to_string())][..])
+        );
+        assert_eq!(key.k(), Some(&[0x00][..]));
+        assert_eq!(encode_to_bytes(&key), bytes);
+    }
+
+    #[test]
+    fn cose_key_invalid() {
+        // missing kty
+        assert!(decode_from_bytes::<CoseKey>(&[0xa1, 0x03, 0x26]).is_err());
+        // duplicated label
+        assert!(decode_from_bytes::<CoseKey>(&[0xa3, 0x01, 0x02, 0x20, 0x01, 0x20, 0x01]).is_err());
+        // text label
+        assert!(decode_from_bytes::<CoseKey>(&[0xa2, 0x01, 0x02, 0x61, b'a', 0x01]).is_err());
+        // wrong type for kid
+        assert!(decode_from_bytes::<CoseKey>(&[0xa2, 0x01, 0x02, 0x02, 0x01]).is_err());
+    }
+}
@@ -1,8 +1,17 @@
-use super::prim::CborDataOf;
+use super::prim::{CborData, CborDataOf};
 use super::reader::{Reader, ReaderError};
-use super::types::{DataOwned, Scalar};
+use super::types::{
+    Array, BoundedBytes, Bytes, DataOwned, Fixed, List, ReservedSimpleValue, Scalar, Text, Type,
+    Value,
+};
 use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::ops::{Range, RangeInclusive};
+use std::rc::Rc;
+use std::time::Duration;
 
 /// Possible errors when decoding an element
 #[derive(Debug, Clone)]
@@ -11,6 +20,8 @@ pub enum DecodeErrorKind {
     ReaderError(ReaderError),
     /// Reader has some trailing data, when trying to decode an element
     ReaderNotTerminated { remaining_bytes: usize },
+    /// Some trailing data follows the decoded element, starting at offset `at`
+    TrailingData { at: usize, remaining_bytes: usize },
     /// Underlying conversion is out of range, it gives the u64 values that was attempted to
     /// be converted, and the range that was expected by the conversion
     OutOfRange { min: u64, max: u64, got: u64 },
@@ -18,6 +29,8 @@ pub enum DecodeErrorKind {
     UnexpectedLength { expected: usize, got: usize },
     /// A custom error for the decoder
     Custom(String),
+    /// None of the alternative decoders succeeded, it gives the error of each attempt in order
+    NoMatchingAlternative(Vec<DecodeError>),
 }
 
 impl DecodeErrorKind {
@@ -27,6 +40,21 @@ impl DecodeErrorKind {
     pub fn context_str(self, s: &'static str) -> DecodeError {
         DecodeError::new_str(s, self)
     }
+
+    // map the error of `Reader::expect_finished`, keeping the details of the trailing data
+    pub(crate) fn not_finished(e: ReaderError) -> Self {
+        match e {
+            ReaderError::NotTerminated {
+                at,
+                remaining_bytes,
+                next_byte: _,
+            } => DecodeErrorKind::TrailingData {
+                at,
+                remaining_bytes,
+            },
+            e => DecodeErrorKind::ReaderError(e),
+        }
+    }
 }
 
 impl std::error::Error for DecodeErrorKind {}
@@ -92,6 +120,29 @@ impl DecodeError {
         &self.error
     }
 
+    /// Return whether the error is caused by truncated data (`ReaderError::DataMissing`),
+    /// in which case decoding again with more data might succeed
+    pub fn is_incomplete(&self) -> bool {
+        matches!(
+            self.error,
+            DecodeErrorKind::ReaderError(ReaderError::DataMissing(_))
+        )
+    }
+
+    /// Return the number of additional bytes needed by the read that failed,
+    /// when the error is caused by truncated data
+    ///
+    /// This is a lower bound, as the rest of the element may need even more data
+    /// once these bytes are available
+    pub fn bytes_needed(&self) -> Option<usize> {
+        match &self.error {
+            DecodeErrorKind::ReaderError(ReaderError::DataMissing(missing)) => {
+                Some(missing.expecting_bytes - missing.got_bytes)
+            }
+            _ => None,
+        }
+    }
+
     /// Return the context from the innermost context, to the outer ones
     pub fn context(&self) -> &[Cow<'static, str>] {
         &self.context
@@ -107,6 +158,39 @@ impl DecodeError {
         }
         s
     }
+
+    /// Return the byte offset in the data associated with the error, when the error has one
+    pub fn offset(&self) -> Option<usize> {
+        match &self.error {
+            DecodeErrorKind::TrailingData { at, .. } => Some(*at),
+            DecodeErrorKind::ReaderError(ReaderError::NotTerminated { at, .. }) => Some(*at),
+            _ => None,
+        }
+    }
+
+    /// Format a multi-line human readable report of the error, made of the context path,
+    /// the error with all its fields, and the byte offset when available
+    ///
+    /// ```text
+    /// failed to decode (u64, u64)->1->u64
+    ///   error: ReaderError(
+    ///       WrongExpectedType {
+    ///           expected: Positive,
+    ///           got: Text,
+    ///       },
+    ///   )
+    /// ```
+    pub fn report(&self) -> String {
+        let mut s = format!("failed to decode {}\n", self.context_as_path());
+        let error = format!("{:#?}", self.error);
+        s.push_str("  error: ");
+        s.push_str(&error.replace('\n', "\n  "));
+        s.push('\n');
+        if let Some(at) = self.offset() {
+            s.push_str(&format!("  at byte offset: {}\n", at));
+        }
+        s
+    }
 }
 
 impl std::error::Error for DecodeError {}
@@ -122,6 +206,41 @@ pub trait Decode: Sized {
     fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError>;
 }
 
+/// Decode trait for an element T that may borrow from the CBOR data of the reader
+///
+/// Any `Decode` type is also `DecodeBorrowed`, and `&'a str` and `&'a [u8]` can be
+/// decoded without allocation from definite Text and Bytes
+pub trait DecodeBorrowed<'a>: Sized {
+    fn decode_borrowed(reader: &mut Reader<'a>) -> Result<Self, DecodeError>;
+}
+
+impl<'a, T: Decode> DecodeBorrowed<'a> for T {
+    fn decode_borrowed(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        <T>::decode(reader)
+    }
+}
+
+impl<'a> DecodeBorrowed<'a> for &'a str {
+    fn decode_borrowed(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        reader
+            .text_str()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())
+    }
+}
+
+impl<'a> DecodeBorrowed<'a> for &'a [u8] {
+    fn decode_borrowed(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        reader
+            .bytes_slice()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())
+    }
+}
+
+/// A function decoding a T from a reader, like `Decode::decode`
+pub type DecodeFn<T> = for<'a> fn(&mut Reader<'a>) -> Result<T, DecodeError>;
+
 /// Decode zero to many Ts in an array
 ///
 /// this is identical to Array::to_vec, but has better error reporting
@@ -133,7 +252,8 @@ pub fn decode_vec<'a, T: Decode>(reader: &mut Reader<'a>) -> Result<Vec<T>, Deco
         .map_err(DecodeErrorKind::ReaderError)
         .map_err(|e| e.context_str("Vec"))?;
     let mut out = Vec::with_capacity(a.len());
-    for (i, mut inner_reader) in a.iter().enumerate() {
+    for (i, slice) in a.elements.iter().enumerate() {
+        let mut inner_reader = reader.nested_reader(slice);
         let v = <T>::decode(&mut inner_reader)
             .map_err(|e| e.push_string(format!("{}", i)).push_str("Vec"))?;
         out.push(v)
@@ -174,6 +294,20 @@ impl Decode for DataOwned {
     }
 }
 
+impl Decode for Value {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let tree = reader
+            .data_recursive()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        // only fails on the non well-formed two bytes encoding of the values 20 to 31
+        Value::try_from(tree).map_err(|ReservedSimpleValue(v)| {
+            DecodeErrorKind::Custom(format!("simple value {} cannot be represented", v))
+                .context::<Self>()
+        })
+    }
+}
+
 impl Decode for bool {
     fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
         reader
@@ -229,6 +363,17 @@ impl Decode for u64 {
     }
 }
 
+/// f64 is decoded from a float of any precision
+impl Decode for f64 {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let float = reader
+            .float()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        Ok(float.to_f64())
+    }
+}
+
 impl Decode for String {
     fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
         let t = reader
@@ -239,6 +384,42 @@ impl Decode for String {
     }
 }
 
+/// Text decoded through the interner of the reader if it has one, see `Reader::with_interner`
+impl Decode for Rc<str> {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let t = reader
+            .text()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        let s = match &t {
+            Text::Imm(td) => Cow::Borrowed(td.as_str()),
+            Text::Chunks(_) => Cow::Owned(t.to_string()),
+        };
+        Ok(match reader.interner() {
+            Some(interner) => interner.intern_str(&s),
+            None => Rc::from(s.as_ref()),
+        })
+    }
+}
+
+/// Bytes decoded through the interner of the reader if it has one, see `Reader::with_interner`
+impl Decode for Rc<[u8]> {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let b = reader
+            .bytes()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        let data = match &b {
+            Bytes::Imm(bd) => Cow::Borrowed(bd.as_slice()),
+            Bytes::Chunks(_) => Cow::Owned(b.to_vec()),
+        };
+        Ok(match reader.interner() {
+            Some(interner) => interner.intern_bytes(&data),
+            None => Rc::from(data.as_ref()),
+        })
+    }
+}
+
 impl<const N: usize> Decode for [u8; N] {
     fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
         let bytes = reader
@@ -260,6 +441,143 @@ impl<const N: usize> Decode for [u8; N] {
     }
 }
 
+/// Decode a CBOR map in any order, duplicated keys are rejected
+impl<K: Decode + Ord, V: Decode> Decode for BTreeMap<K, V> {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let map = reader
+            .map()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        let mut out = BTreeMap::new();
+        for (i, (k, v)) in map.elements.iter().enumerate() {
+            let key = reader
+                .nested_reader(k)
+                .decode()
+                .map_err(|e| e.push_string(format!("key {}", i)).push::<Self>())?;
+            let value = reader
+                .nested_reader(v)
+                .decode()
+                .map_err(|e| e.push_string(format!("value {}", i)).push::<Self>())?;
+            if out.insert(key, value).is_some() {
+                return Err(
+                    DecodeErrorKind::Custom(format!("duplicated key {}", i)).context::<Self>()
+                );
+            }
+        }
+        Ok(out)
+    }
+}
+
+// tag of a mathematical finite set, from the IANA CBOR tags registry
+const TAG_SET: u64 = 258;
+
+// decode a set from an array, optionally wrapped in the set tag 258, giving each element
+// to `insert` which return false when the element is already in the set
+fn decode_set<S, T: Decode>(
+    reader: &mut Reader,
+    mut insert: impl FnMut(T) -> bool,
+) -> Result<(), DecodeError> {
+    let ty = reader
+        .peek_type()
+        .map_err(DecodeErrorKind::ReaderError)
+        .map_err(|e| e.context::<S>())?;
+    let array = if ty == Type::Tag {
+        let tag = reader
+            .tag()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<S>())?;
+        if tag.value() != TAG_SET {
+            return Err(DecodeErrorKind::ReaderError(ReaderError::WrongExpectedTag {
+                expected: TAG_SET,
+                got: tag.value(),
+            })
+            .context::<S>());
+        }
+        tag.read_data(|reader| reader.array())
+    } else {
+        reader.array()
+    }
+    .map_err(DecodeErrorKind::ReaderError)
+    .map_err(|e| e.context::<S>())?;
+    for (i, slice) in array.elements.iter().enumerate() {
+        let v = <T>::decode(&mut reader.nested_reader(slice))
+            .map_err(|e| e.push_string(format!("{}", i)).push::<S>())?;
+        if !insert(v) {
+            return Err(DecodeErrorKind::Custom(format!("duplicated element {}", i)).context::<S>());
+        }
+    }
+    Ok(())
+}
+
+/// BTreeSet is decoded from an array, optionally wrapped in the set tag 258,
+/// rejecting duplicated elements
+impl<T: Decode + Ord> Decode for BTreeSet<T> {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let mut out = BTreeSet::new();
+        decode_set::<Self, T>(reader, |v| out.insert(v))?;
+        Ok(out)
+    }
+}
+
+/// HashSet is decoded from an array, optionally wrapped in the set tag 258,
+/// rejecting duplicated elements
+impl<T: Decode + Eq + Hash, S: BuildHasher + Default> Decode for HashSet<T, S> {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let mut out = HashSet::default();
+        decode_set::<Self, T>(reader, |v| out.insert(v))?;
+        Ok(out)
+    }
+}
+
+impl<T: Decode> Decode for List<T> {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let array = reader
+            .array()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        let mut elements = Vec::with_capacity(array.len());
+        for (i, slice) in array.elements.iter().enumerate() {
+            let v = <T>::decode(&mut reader.nested_reader(slice))
+                .map_err(|e| e.push_string(format!("{}", i)).push::<Self>())?;
+            elements.push(v)
+        }
+        Ok(List {
+            len_encoding: array.struct_len(),
+            elements,
+        })
+    }
+}
+
+// the total length is checked against N before copying the bytes into the fixed size buffer,
+// but reading indefinite bytes still allocates the list of their chunks
+impl<const N: usize> Decode for BoundedBytes<N> {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let bytes = reader
+            .bytes()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        let len = bytes.len();
+        if len > N {
+            return Err(
+                DecodeErrorKind::ReaderError(ReaderError::TooLarge { max: N, got: len })
+                    .context::<Self>(),
+            );
+        }
+        let mut out = BoundedBytes::new();
+        match &bytes {
+            Bytes::Imm(bd) => {
+                out.extend_from_slice(bd.as_slice());
+            }
+            Bytes::Chunks(chunks) => {
+                for bd in chunks {
+                    out.extend_from_slice(bd.as_slice());
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
 impl Decode for Vec<u8> {
     fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
         let t = reader
@@ -270,6 +588,249 @@ impl Decode for Vec<u8> {
     }
 }
 
+impl Decode for Ipv4Addr {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let octets: [u8; 4] = reader.decode().map_err(|e| e.push::<Self>())?;
+        Ok(Ipv4Addr::from(octets))
+    }
+}
+
+impl Decode for Ipv6Addr {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let octets: [u8; 16] = reader.decode().map_err(|e| e.push::<Self>())?;
+        Ok(Ipv6Addr::from(octets))
+    }
+}
+
+// the address family is given by the length of the bytes, 4 for IPv4 and 16 for IPv6
+impl Decode for IpAddr {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let bytes = reader
+            .bytes()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        let octets = bytes.to_vec();
+        if let Ok(v4) = <[u8; 4]>::try_from(octets.as_slice()) {
+            Ok(IpAddr::V4(Ipv4Addr::from(v4)))
+        } else if let Ok(v6) = <[u8; 16]>::try_from(octets.as_slice()) {
+            Ok(IpAddr::V6(Ipv6Addr::from(v6)))
+        } else {
+            Err(DecodeErrorKind::Custom(format!(
+                "expecting 4 or 16 bytes for IP address, got {}",
+                octets.len()
+            ))
+            .context::<Self>())
+        }
+    }
+}
+
+// a duration is decoded from the array [seconds, nanoseconds], see the `Encode` instance
+impl Decode for Duration {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let array = reader
+            .array()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        if array.len() != 2 {
+            return Err(DecodeErrorKind::UnexpectedLength {
+                expected: 2,
+                got: array.len(),
+            }
+            .context::<Self>());
+        }
+        let secs: u64 = reader
+            .decode_nested(array[0])
+            .map_err(|e| e.push_str("secs").push::<Self>())?;
+        let nanos: u32 = reader
+            .decode_nested(array[1])
+            .map_err(|e| e.push_str("nanos").push::<Self>())?;
+        if nanos >= 1_000_000_000 {
+            return Err(DecodeErrorKind::OutOfRange {
+                min: 0,
+                max: 999_999_999,
+                got: nanos as u64,
+            }
+            .context::<Self>());
+        }
+        Ok(Duration::new(secs, nanos))
+    }
+}
+
+/// Fixed is decoded from the Positive or Negative integer of the scaled value,
+/// which need to fit in a i64
+impl<const SCALE: u32> Decode for Fixed<SCALE> {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let scalar = reader
+            .scalar()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        match scalar.to_i64() {
+            Some(v) => Ok(Fixed(v)),
+            None => Err(DecodeErrorKind::Custom(format!(
+                "scaled value {:?} out of the i64 range",
+                scalar
+            ))
+            .context::<Self>()),
+        }
+    }
+}
+
+// decode the array `[start, end]` of a range
+fn decode_range_bounds<T>(reader: &mut Reader) -> Result<(u64, u64), DecodeError> {
+    let array = reader
+        .array()
+        .map_err(DecodeErrorKind::ReaderError)
+        .map_err(|e| e.context::<T>())?;
+    if array.len() != 2 {
+        return Err(DecodeErrorKind::UnexpectedLength {
+            expected: 2,
+            got: array.len(),
+        }
+        .context::<T>());
+    }
+    let start = reader
+        .decode_nested(array[0])
+        .map_err(|e| e.push_str("start").push::<T>())?;
+    let end = reader
+        .decode_nested(array[1])
+        .map_err(|e| e.push_str("end").push::<T>())?;
+    Ok((start, end))
+}
+
+/// Range is decoded from an array of 2 elements `[start, end]`, empty ranges are accepted
+impl Decode for Range<u64> {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let (start, end) = decode_range_bounds::<Self>(reader)?;
+        Ok(start..end)
+    }
+}
+
+/// RangeInclusive is decoded from an array of 2 elements `[start, end]`, empty ranges are accepted
+impl Decode for RangeInclusive<u64> {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let (start, end) = decode_range_bounds::<Self>(reader)?;
+        Ok(start..=end)
+    }
+}
+
+/// Result is decoded from an array of 2 elements `[0, ok]` or `[1, err]`
+impl<T: Decode, E: Decode> Decode for Result<T, E> {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let array = reader
+            .array()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        if array.len() != 2 {
+            return Err(DecodeErrorKind::UnexpectedLength {
+                expected: 2,
+                got: array.len(),
+            }
+            .context::<Self>());
+        }
+        let variant: u64 = reader
+            .decode_nested(array[0])
+            .map_err(|e| e.push::<Self>())?;
+        match variant {
+            0 => Ok(Ok(reader
+                .decode_nested(array[1])
+                .map_err(|e| e.push_str("Ok").push::<Self>())?)),
+            1 => Ok(Err(reader
+                .decode_nested(array[1])
+                .map_err(|e| e.push_str("Err").push::<Self>())?)),
+            got => Err(DecodeErrorKind::OutOfRange {
+                min: 0,
+                max: 1,
+                got,
+            }
+            .context::<Self>()),
+        }
+    }
+}
+
+// tuple is decoded from an array of exactly the number of elements of the tuple
+macro_rules! tuple_decode {
+    ($len:literal; $($t:ident $i:tt),+) => {
+        impl<$($t: Decode),+> Decode for ($($t,)+) {
+            fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+                let array = reader
+                    .array()
+                    .map_err(DecodeErrorKind::ReaderError)
+                    .map_err(|e| e.context::<Self>())?;
+                if array.len() != $len {
+                    return Err(DecodeErrorKind::UnexpectedLength {
+                        expected: $len,
+                        got: array.len(),
+                    }
+                    .context::<Self>());
+                }
+                Ok(($(
+                    reader
+                        .decode_nested::<$t>(array[$i])
+                        .map_err(|e| e.push_str(stringify!($i)).push::<Self>())?,
+                )+))
+            }
+        }
+    };
+}
+
+impl Decode for () {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let array = reader
+            .array()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        if array.len() != 0 {
+            return Err(DecodeErrorKind::UnexpectedLength {
+                expected: 0,
+                got: array.len(),
+            }
+            .context::<Self>());
+        }
+        Ok(())
+    }
+}
+
+tuple_decode!(1; A 0);
+tuple_decode!(2; A 0, B 1);
+tuple_decode!(3; A 0, B 1, C 2);
+tuple_decode!(4; A 0, B 1, C 2, D 3);
+tuple_decode!(5; A 0, B 1, C 2, D 3, E 4);
+tuple_decode!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+
+/// Tuple of decodable elements, that can be read from an array of the same length
+/// with `Reader::array_tuple`
+pub trait ArrayTuple: Sized {
+    /// Number of elements of the tuple
+    const LEN: usize;
+
+    /// Decode each element of the tuple from an array of exactly `LEN` elements,
+    /// read from `reader`
+    fn decode_elements<'a>(reader: &Reader<'a>, array: &Array<'a>) -> Result<Self, DecodeError>;
+}
+
+macro_rules! array_tuple {
+    ($len:literal; $($t:ident $i:tt),+) => {
+        impl<$($t: Decode),+> ArrayTuple for ($($t,)+) {
+            const LEN: usize = $len;
+
+            fn decode_elements<'a>(reader: &Reader<'a>, array: &Array<'a>) -> Result<Self, DecodeError> {
+                Ok(($(
+                    reader
+                        .decode_nested::<$t>(array[$i])
+                        .map_err(|e| e.push_str(concat!("element ", stringify!($i))).push::<Self>())?,
+                )+))
+            }
+        }
+    };
+}
+
+array_tuple!(1; A 0);
+array_tuple!(2; A 0, B 1);
+array_tuple!(3; A 0, B 1, C 2);
+array_tuple!(4; A 0, B 1, C 2, D 3);
+array_tuple!(5; A 0, B 1, C 2, D 3, E 4);
+array_tuple!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+
 impl Decode for Scalar {
     fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
         reader
@@ -279,6 +840,17 @@ impl Decode for Scalar {
     }
 }
 
+/// Capture the next CBOR element as is, without interpreting its content
+impl Decode for CborData {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        reader
+            .raw_slice()
+            .map(|slice| slice.to_owned())
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())
+    }
+}
+
 impl<T: Decode> Decode for CborDataOf<T> {
     fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
         reader.exact_decodable_data()
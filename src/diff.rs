@@ -0,0 +1,162 @@
+//! Structural difference between two CBOR elements, for debugging
+use super::decode::{DecodeError, DecodeErrorKind};
+use super::reader::{Reader, ReaderError, ReaderPolicy, MAX_RECURSION_DEPTH};
+use super::types::*;
+use std::fmt;
+
+/// One difference between two CBOR elements
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    /// Path of the element from the root, e.g. `["root", "map[key 3]", "array[1]"]`
+    pub path: Vec<String>,
+    /// Element of the left side, or None if the element only exists on the right side
+    pub left: Option<DataOwned>,
+    /// Element of the right side, or None if the element only exists on the left side
+    pub right: Option<DataOwned>,
+}
+
+impl fmt::Display for DiffEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |d: &Option<DataOwned>| match d {
+            None => "<missing>".to_string(),
+            Some(d) => summary(&d.borrow()),
+        };
+        write!(
+            f,
+            "{}: {} vs {}",
+            self.path.join("->"),
+            side(&self.left),
+            side(&self.right)
+        )
+    }
+}
+
+/// Compare two CBOR elements and return the path qualified differences between them
+///
+/// Arrays are compared index by index, maps are compared by keys (as encoded) whatever
+/// the order of the entries, and tags by their tagged element. Any other element is compared
+/// exactly, including its encoding: a non-canonical integer is different from its canonical form.
+///
+/// Elements nesting more than `MAX_RECURSION_DEPTH` arrays, maps and tags are rejected
+pub fn diff(a: &[u8], b: &[u8]) -> Result<Vec<DiffEntry>, DecodeError> {
+    let a = decode_bounded(a)?;
+    let b = decode_bounded(b)?;
+    let mut out = Vec::new();
+    let mut path = vec!["root".to_string()];
+    diff_data(&mut path, &a.borrow(), &b.borrow(), &mut out)
+        .map_err(DecodeErrorKind::ReaderError)
+        .map_err(|e| e.context::<DiffEntry>())?;
+    Ok(out)
+}
+
+// decode one element, checking its depth once so that diff_data recursion is bounded
+fn decode_bounded(bytes: &[u8]) -> Result<DataOwned, DecodeError> {
+    let policy = ReaderPolicy {
+        max_depth: MAX_RECURSION_DEPTH,
+        ..ReaderPolicy::default()
+    };
+    let mut reader = Reader::with_policy(bytes, policy);
+    let data: DataOwned = reader.decode()?;
+    reader
+        .expect_finished()
+        .map_err(DecodeErrorKind::not_finished)
+        .map_err(|e| e.context::<DataOwned>())?;
+    Ok(data)
+}
+
+fn diff_data(
+    path: &mut Vec<String>,
+    a: &Data,
+    b: &Data,
+    out: &mut Vec<DiffEntry>,
+) -> Result<(), ReaderError> {
+    match (a, b) {
+        (Data::Array(x), Data::Array(y)) => {
+            for i in 0..x.len().max(y.len()) {
+                path.push(format!("array[{}]", i));
+                match (x.get(i), y.get(i)) {
+                    (Some(mut r1), Some(mut r2)) => diff_data(path, &r1.data()?, &r2.data()?, out)?,
+                    (r1, r2) => {
+                        let left = r1.map(|mut r| r.data().map(|d| d.owned())).transpose()?;
+                        let right = r2.map(|mut r| r.data().map(|d| d.owned())).transpose()?;
+                        push_entry(out, path, left, right)
+                    }
+                }
+                path.pop();
+            }
+        }
+        (Data::Map(x), Data::Map(y)) => {
+            for i in 0..x.len() {
+                let (k, v) = x[i];
+                path.push(format!("map[key {}]", summary(&k.reader().data()?)));
+                match (0..y.len()).map(|j| y[j]).find(|(k2, _)| k2 == &k) {
+                    Some((_, v2)) => {
+                        diff_data(path, &v.reader().data()?, &v2.reader().data()?, out)?
+                    }
+                    None => push_entry(out, path, Some(v.reader().data()?.owned()), None),
+                }
+                path.pop();
+            }
+            for j in 0..y.len() {
+                let (k2, v2) = y[j];
+                if (0..x.len()).any(|i| x[i].0 == k2) {
+                    continue;
+                }
+                path.push(format!("map[key {}]", summary(&k2.reader().data()?)));
+                push_entry(out, path, None, Some(v2.reader().data()?.owned()));
+                path.pop();
+            }
+        }
+        (Data::Tag(x), Data::Tag(y)) if x.value() == y.value() => {
+            path.push(format!("tag({})", x.value()));
+            diff_data(path, &x.reader().data()?, &y.reader().data()?, out)?;
+            path.pop();
+        }
+        (a, b) => {
+            if a != b {
+                push_entry(out, path, Some(a.owned()), Some(b.owned()))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn push_entry(
+    out: &mut Vec<DiffEntry>,
+    path: &[String],
+    left: Option<DataOwned>,
+    right: Option<DataOwned>,
+) {
+    out.push(DiffEntry {
+        path: path.to_vec(),
+        left,
+        right,
+    })
+}
+
+// short representation of an element, without the content of arrays, maps and tags
+fn summary(d: &Data) -> String {
+    match d {
+        Data::Positive(v) => format!("{}", v.to_u64()),
+        Data::Negative(v) => format!("{}", -1 - (v.negative_u64() as i128)),
+        Data::Float(v) => format!("{:?}", v.to_f64()),
+        Data::Byte(v) => format!("simple({})", v.to_u8()),
+        Data::Simple(v) => format!("simple({})", v),
+        Data::Bytes(v) => {
+            let hex = v
+                .to_vec()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>();
+            format!("h'{}'", hex)
+        }
+        Data::Text(v) => format!("{:?}", v.to_string()),
+        Data::Array(v) => format!("array({})", v.len()),
+        Data::Map(v) => format!("map({})", v.len()),
+        Data::Tag(v) => format!("tag({})", v.value()),
+        Data::True => "true".to_string(),
+        Data::False => "false".to_string(),
+        Data::Null => "null".to_string(),
+        Data::Undefined => "undefined".to_string(),
+    }
+}
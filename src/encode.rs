@@ -2,12 +2,23 @@ use super::header::HeaderValue;
 use super::prim::{CborData, CborDataOf, CborSlice, CborSliceOf};
 use super::types::*;
 use super::writer::Writer;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::ops::{Range, RangeInclusive};
+use std::rc::Rc;
+use std::time::Duration;
 
 /// Generic Encode trait to write an element T into the CBOR writer
 pub trait Encode {
     fn encode(&self, writer: &mut Writer);
 }
 
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, writer: &mut Writer) {
+        (*self).encode(writer)
+    }
+}
+
 // *** CBOR types ***
 
 impl Encode for Positive {
@@ -70,6 +81,12 @@ impl<'a> Encode for Map<'a> {
     }
 }
 
+impl Encode for MapOwned {
+    fn encode(&self, writer: &mut Writer) {
+        writer.map(&self.borrow())
+    }
+}
+
 impl<'a> Encode for Tag<'a> {
     fn encode(&self, writer: &mut Writer) {
         writer.tag(self)
@@ -90,6 +107,42 @@ impl Encode for DataOwned {
 
 // *** native rust types ***
 
+/// Value is encoded in its canonical form, with definite structures and floats in the
+/// shortest precision representing them exactly
+impl Encode for Value {
+    fn encode(&self, writer: &mut Writer) {
+        match self {
+            Value::Unsigned(v) => writer.positive(Positive::canonical(*v)),
+            Value::Negative(v) => writer.negative(Negative::canonical(*v)),
+            Value::Bytes(v) => writer.bytes(&Bytes::from_slice(v)),
+            Value::Text(v) => writer.text(&Text::from_str(v)),
+            Value::Array(v) => {
+                writer.array_build(StructureLength::from(v.len() as u64), |writer| {
+                    for e in v.iter() {
+                        writer.encode(e)
+                    }
+                })
+            }
+            Value::Map(v) => writer.map_build(StructureLength::from(v.len() as u64), |writer| {
+                for (k, v) in v.iter() {
+                    writer.encode(k);
+                    writer.encode(v);
+                }
+            }),
+            Value::Tag(tag, v) => {
+                writer.tag_build(TagValue::from_u64(*tag), |writer| writer.encode(v.as_ref()))
+            }
+            Value::Float(v) => writer.float(Float::from_f64_exact(*v)),
+            Value::Bool(v) => writer.bool(*v),
+            Value::Null => writer.constant(Constant::Null),
+            Value::Undefined => writer.constant(Constant::Undefined),
+            // a SimpleValue is never between 20 and 31
+            Value::Simple(v) if v.to_u8() < 0x14 => writer.byte(Byte::canonical(v.to_u8())),
+            Value::Simple(v) => writer.simple(v.to_u8()),
+        }
+    }
+}
+
 impl Encode for bool {
     fn encode(&self, writer: &mut Writer) {
         writer.bool(*self)
@@ -120,6 +173,13 @@ impl Encode for u64 {
     }
 }
 
+/// f64 is always encoded as a double precision float, keeping its exact bits
+impl Encode for f64 {
+    fn encode(&self, writer: &mut Writer) {
+        writer.float(Float::from_f64_exact(*self))
+    }
+}
+
 impl Encode for String {
     fn encode(&self, writer: &mut Writer) {
         writer.text(&Text::from_str(self))
@@ -138,12 +198,209 @@ impl Encode for [u8] {
     }
 }
 
+impl Encode for Rc<str> {
+    fn encode(&self, writer: &mut Writer) {
+        writer.text(&Text::from_str(self))
+    }
+}
+
+impl Encode for Rc<[u8]> {
+    fn encode(&self, writer: &mut Writer) {
+        writer.bytes(&Bytes::from_slice(self))
+    }
+}
+
+/// BTreeMap is encoded as a definite map, with the entries sorted in the bytewise lexicographic
+/// order of the encoded keys (RFC 8949 deterministic encoding), whatever the `Ord` of the keys
+impl<K: Encode, V: Encode> Encode for BTreeMap<K, V> {
+    fn encode(&self, writer: &mut Writer) {
+        let mut entries = self
+            .iter()
+            .map(|(k, v)| {
+                let mut key_writer = Writer::new();
+                key_writer.encode(k);
+                (key_writer.finalize(), v)
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+        writer.map_build(StructureLength::from(entries.len() as u64), |writer| {
+            for (k, v) in entries.iter() {
+                writer.append_slice(k);
+                writer.encode(v);
+            }
+        })
+    }
+}
+
+// encode the elements of a set as a definite array, sorted in the bytewise lexicographic
+// order of the encoded elements, which doesn't depend on the iteration order of the set
+fn encode_set<'a, T: Encode + 'a>(writer: &mut Writer, elements: impl Iterator<Item = &'a T>) {
+    let mut elements = elements
+        .map(|v| {
+            let mut element_writer = Writer::new();
+            element_writer.encode(v);
+            element_writer.finalize()
+        })
+        .collect::<Vec<_>>();
+    elements.sort();
+    writer.array_build(StructureLength::from(elements.len() as u64), |writer| {
+        for v in elements.iter() {
+            writer.append_slice(v);
+        }
+    })
+}
+
+/// BTreeSet is encoded as a definite array, with the elements sorted in the bytewise
+/// lexicographic order of the encoded elements, whatever the `Ord` of the elements
+impl<T: Encode> Encode for BTreeSet<T> {
+    fn encode(&self, writer: &mut Writer) {
+        encode_set(writer, self.iter())
+    }
+}
+
+/// HashSet is encoded as a definite array, with the elements sorted in the bytewise
+/// lexicographic order of the encoded elements, so the encoding is deterministic
+impl<T: Encode, S> Encode for HashSet<T, S> {
+    fn encode(&self, writer: &mut Writer) {
+        encode_set(writer, self.iter())
+    }
+}
+
+impl<T: Encode> Encode for List<T> {
+    fn encode(&self, writer: &mut Writer) {
+        let len_encoding = match self.len_encoding {
+            StructureLength::Definite(v) if v.to_size() != self.elements.len() => {
+                StructureLength::from(self.elements.len() as u64)
+            }
+            len_encoding => len_encoding,
+        };
+        writer.array_build(len_encoding, |writer| {
+            for element in self.elements.iter() {
+                writer.encode(element)
+            }
+        })
+    }
+}
+
+impl<const N: usize> Encode for BoundedBytes<N> {
+    fn encode(&self, writer: &mut Writer) {
+        writer.bytes(&Bytes::from_slice(self.as_slice()))
+    }
+}
+
 impl<const N: usize> Encode for [u8; N] {
     fn encode(&self, writer: &mut Writer) {
         writer.bytes(&Bytes::from_slice(self))
     }
 }
 
+impl Encode for Ipv4Addr {
+    fn encode(&self, writer: &mut Writer) {
+        writer.encode(&self.octets())
+    }
+}
+
+impl Encode for Ipv6Addr {
+    fn encode(&self, writer: &mut Writer) {
+        writer.encode(&self.octets())
+    }
+}
+
+impl Encode for IpAddr {
+    fn encode(&self, writer: &mut Writer) {
+        match self {
+            IpAddr::V4(v4) => v4.encode(writer),
+            IpAddr::V6(v6) => v6.encode(writer),
+        }
+    }
+}
+
+// a duration is encoded as the array [seconds, nanoseconds], which is exact
+// contrary to a float of the total seconds
+impl Encode for Duration {
+    fn encode(&self, writer: &mut Writer) {
+        writer.array_build(StructureLength::from(2), |writer| {
+            writer.encode(&self.as_secs());
+            writer.encode(&self.subsec_nanos());
+        })
+    }
+}
+
+/// Fixed is encoded as the Positive or Negative integer of the scaled value
+impl<const SCALE: u32> Encode for Fixed<SCALE> {
+    fn encode(&self, writer: &mut Writer) {
+        if self.0 >= 0 {
+            writer.positive(Positive::canonical(self.0 as u64));
+        } else {
+            // CBOR negative n represent the integer -1 - n
+            writer.negative(Negative::canonical(!self.0 as u64));
+        }
+    }
+}
+
+/// Range is encoded as an array of 2 elements `[start, end]`, where end is excluded
+impl Encode for Range<u64> {
+    fn encode(&self, writer: &mut Writer) {
+        writer.array_build(StructureLength::from(2), |writer| {
+            writer.encode(&self.start);
+            writer.encode(&self.end);
+        })
+    }
+}
+
+/// RangeInclusive is encoded as an array of 2 elements `[start, end]`, where end is included
+impl Encode for RangeInclusive<u64> {
+    fn encode(&self, writer: &mut Writer) {
+        writer.array_build(StructureLength::from(2), |writer| {
+            writer.encode(self.start());
+            writer.encode(self.end());
+        })
+    }
+}
+
+/// Result is encoded as an array of 2 elements `[0, ok]` or `[1, err]`
+impl<T: Encode, E: Encode> Encode for Result<T, E> {
+    fn encode(&self, writer: &mut Writer) {
+        writer.array_build(StructureLength::from(2), |writer| match self {
+            Ok(v) => {
+                writer.encode(&0u64);
+                writer.encode(v);
+            }
+            Err(e) => {
+                writer.encode(&1u64);
+                writer.encode(e);
+            }
+        })
+    }
+}
+
+// tuple is encoded as a definite array of its elements in order
+macro_rules! tuple_encode {
+    ($len:literal; $($t:ident $i:tt),+) => {
+        impl<$($t: Encode),+> Encode for ($($t,)+) {
+            fn encode(&self, writer: &mut Writer) {
+                writer.array_build(StructureLength::from($len), |writer| {
+                    $( writer.encode(&self.$i); )+
+                })
+            }
+        }
+    };
+}
+
+// the unit is encoded as an empty array, like a tuple of no elements
+impl Encode for () {
+    fn encode(&self, writer: &mut Writer) {
+        writer.array_build(StructureLength::from(0), |_| {})
+    }
+}
+
+tuple_encode!(1; A 0);
+tuple_encode!(2; A 0, B 1);
+tuple_encode!(3; A 0, B 1, C 2);
+tuple_encode!(4; A 0, B 1, C 2, D 3);
+tuple_encode!(5; A 0, B 1, C 2, D 3, E 4);
+tuple_encode!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+
 // don't need the bound to encode, but just enforce it for soudness of `CborDataOf`
 impl<T: Encode> Encode for CborDataOf<T> {
     fn encode(&self, writer: &mut Writer) {
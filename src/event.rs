@@ -0,0 +1,37 @@
+//! CBOR events for pull parsing
+use super::types::*;
+
+/// One CBOR event, as returned by `Reader::next_event`
+///
+/// Scalar elements are returned as one event, whereas structures are returned as
+/// a start event followed by the events of their elements. Definite arrays and maps
+/// terminate implicitly after their number of elements, indefinite structures
+/// terminate with a `Break` event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event<'a> {
+    Positive(Positive),
+    Negative(Negative),
+    /// Definite bytes, or one chunk of an indefinite bytes
+    Bytes(BytesData<'a>),
+    /// Start of an indefinite bytes, followed by bytes chunks and a break
+    BytesStart,
+    /// Definite text, or one chunk of an indefinite text
+    Text(TextData<'a>),
+    /// Start of an indefinite text, followed by text chunks and a break
+    TextStart,
+    /// Start of an array, followed by its elements
+    ArrayStart(StructureLength),
+    /// Start of a map, followed by its keys and values
+    MapStart(StructureLength),
+    /// Tag, followed by the tagged element
+    Tag(TagValue),
+    Float(Float),
+    Byte(Byte),
+    Simple(u8),
+    True,
+    False,
+    Null,
+    Undefined,
+    /// End of an indefinite bytes, text, array or map
+    Break,
+}
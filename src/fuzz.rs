@@ -0,0 +1,96 @@
+//! Invariants to check from a fuzzing target
+//!
+//! ```
+//! // fuzz/fuzz_targets/roundtrip.rs
+//! // libfuzzer_sys::fuzz_target!(|data: &[u8]| assert!(cbored::fuzz::roundtrip_check(data)));
+//! assert!(cbored::fuzz::roundtrip_check(&[0x82, 0x18, 0x01, 0x9f, 0xff]));
+//! ```
+use super::decode::Decode;
+use super::encode::Encode;
+use super::reader::Reader;
+use super::types::DataOwned;
+use super::writer::Writer;
+
+/// Check the round trip invariants of one CBOR element at the start of `data`
+///
+/// Any data that doesn't start with a valid CBOR element is not considered,
+/// and return true. Otherwise the element is decoded as a `DataOwned` and re-encoded,
+/// which need to give back the exact same bytes as the original element, canonical or not,
+/// and decoding those bytes need to give back the same `DataOwned`.
+///
+/// Returns false if any of the invariants doesn't hold
+pub fn roundtrip_check(data: &[u8]) -> bool {
+    let mut reader = Reader::new(data);
+    let first = match DataOwned::decode(&mut reader) {
+        Err(_) => return true,
+        Ok(first) => first,
+    };
+    let consumed = &data[..data.len() - reader.remaining_bytes()];
+
+    let mut writer = Writer::new();
+    first.encode(&mut writer);
+    let encoded = writer.finalize();
+    if encoded != consumed {
+        return false;
+    }
+
+    let mut reader = Reader::new(&encoded);
+    match DataOwned::decode(&mut reader) {
+        Ok(second) => second == first && reader.expect_finished().is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // simple deterministic xorshift generator, to not require a random dependency
+    fn pseudo_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    fn roundtrip_vectors() {
+        let vectors: &[&[u8]] = &[
+            &[0x00],
+            &[0x19, 0x00, 0x01],
+            &[0x3b, 0, 0, 0, 0, 0, 0, 0, 1],
+            &[0x5f, 0x41, 0x01, 0x42, 0x02, 0x03, 0xff],
+            &[0x7f, 0x61, 0x61, 0xff],
+            &[0x9f, 0x01, 0x82, 0x02, 0x03, 0xff],
+            &[0xbf, 0x01, 0x02, 0xff],
+            &[0xc2, 0x41, 0x01],
+            &[0xf9, 0x3c, 0x00],
+            &[0xfa, 0x3f, 0x80, 0x00, 0x00],
+            &[0xf8, 0x20],
+            &[0xf4, 0xf5, 0xf6, 0xf7],
+            &[0x01, 0x02],
+            // not valid CBOR
+            &[],
+            &[0x1c],
+            &[0x82, 0x01],
+            &[0xff],
+        ];
+        for v in vectors {
+            assert!(roundtrip_check(v), "vector {:?}", v);
+        }
+    }
+
+    #[test]
+    fn roundtrip_pseudo_random() {
+        let mut state = 0x2545f4914f6cdd1du64;
+        for _ in 0..20000 {
+            let mut data = Vec::new();
+            let len = (state % 12) as usize;
+            for _ in 0..len {
+                data.push(pseudo_random(&mut state) as u8);
+            }
+            assert!(roundtrip_check(&data), "data {:?}", data);
+            state = state.wrapping_add(1);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Writer hashing the CBOR data as it is encoded
+use super::encode::Encode;
+use super::writer::Writer;
+use digest::{Digest, Output};
+
+/// CBOR Writer feeding every written byte into a digest
+///
+/// The bytes are hashed after each call to `encode` or `write`, so that the
+/// hash is computed along the encoding instead of in a second pass over the data
+pub struct HashingWriter<D: Digest> {
+    writer: Writer,
+    digest: D,
+    hashed: usize,
+}
+
+impl<D: Digest> HashingWriter<D> {
+    /// Create a new hashing writer with a fresh digest
+    pub fn new() -> Self {
+        Self {
+            writer: Writer::new(),
+            digest: D::new(),
+            hashed: 0,
+        }
+    }
+
+    // hash the bytes written since the last update
+    fn update(&mut self) {
+        let written = self.writer.written();
+        self.digest.update(&written[self.hashed..]);
+        self.hashed = written.len();
+    }
+
+    /// Write a T encodable type in the writer, and hash it
+    pub fn encode<T: Encode + ?Sized>(&mut self, t: &T) {
+        self.writer.encode(t);
+        self.update()
+    }
+
+    /// Write CBOR elements with the underlying writer, and hash them
+    pub fn write<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Writer),
+    {
+        f(&mut self.writer);
+        self.update()
+    }
+
+    /// Finalize the writer and the digest, returning the encoded bytes and their hash
+    pub fn finalize(self) -> (Vec<u8>, Output<D>) {
+        (self.writer.finalize(), self.digest.finalize())
+    }
+}
+
+impl<D: Digest> Default for HashingWriter<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode_to_bytes;
+    use sha2::Sha256;
+
+    #[test]
+    fn hash_as_encoded() {
+        let mut writer = HashingWriter::<Sha256>::new();
+        writer.encode(&1u64);
+        writer.write(|w| w.bool(true));
+        writer.encode(&"text".to_string());
+        let (bytes, hash) = writer.finalize();
+
+        let mut expected = encode_to_bytes(&1u64);
+        expected.extend_from_slice(&[0xf5]);
+        expected.extend_from_slice(&encode_to_bytes(&"text".to_string()));
+        assert_eq!(bytes, expected);
+        assert_eq!(hash, Sha256::digest(&expected));
+    }
+}
@@ -0,0 +1,73 @@
+//! Encode and Decode for `IndexMap`, preserving the order of the map elements
+use super::decode::{Decode, DecodeError, DecodeErrorKind};
+use super::encode::Encode;
+use super::reader::Reader;
+use super::types::StructureLength;
+use super::writer::Writer;
+use indexmap::IndexMap;
+use std::hash::{BuildHasher, Hash};
+
+/// Encode the map as a definite CBOR map, with the elements in insertion order
+impl<K: Encode, V: Encode, S> Encode for IndexMap<K, V, S> {
+    fn encode(&self, writer: &mut Writer) {
+        writer.map_build(StructureLength::from(self.len() as u64), |writer| {
+            for (k, v) in self.iter() {
+                writer.encode(k);
+                writer.encode(v);
+            }
+        })
+    }
+}
+
+/// Decode a CBOR map, keeping the elements in the order they appear in the CBOR data
+///
+/// Duplicated keys are rejected
+impl<K: Decode + Hash + Eq, V: Decode, S: BuildHasher + Default> Decode for IndexMap<K, V, S> {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let map = reader
+            .map()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        let mut out = IndexMap::with_capacity_and_hasher(map.len(), S::default());
+        for (i, (k, v)) in map.elements.iter().enumerate() {
+            let key = reader
+                .nested_reader(k)
+                .decode()
+                .map_err(|e| e.push_string(format!("key {}", i)).push::<Self>())?;
+            let value = reader
+                .nested_reader(v)
+                .decode()
+                .map_err(|e| e.push_string(format!("value {}", i)).push::<Self>())?;
+            if out.insert(key, value).is_some() {
+                return Err(
+                    DecodeErrorKind::Custom(format!("duplicated key {}", i)).context::<Self>()
+                );
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_from_bytes, encode_to_bytes};
+
+    #[test]
+    fn order_preserved() {
+        let mut map = IndexMap::<u64, String>::new();
+        map.insert(2, "b".to_string());
+        map.insert(1, "a".to_string());
+        let bytes = encode_to_bytes(&map);
+        assert_eq!(bytes, [0xa2, 0x02, 0x61, 0x62, 0x01, 0x61, 0x61]);
+        let decoded: IndexMap<u64, String> = decode_from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.keys().collect::<Vec<_>>(), [&2, &1]);
+        assert_eq!(decoded, map);
+    }
+
+    #[test]
+    fn duplicated_key() {
+        let bytes = [0xa2, 0x01, 0x61, 0x61, 0x01, 0x61, 0x62];
+        assert!(decode_from_bytes::<IndexMap<u64, String>>(&bytes).is_err());
+    }
+}
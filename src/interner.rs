@@ -0,0 +1,130 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/// Set of the texts and bytes already decoded, shared by the readers created with
+/// `Reader::with_interner`
+///
+/// Decoding a `Rc<str>` or a `Rc<[u8]>` with such a reader returns the already known
+/// value when the same content was decoded before, instead of allocating it again,
+/// which reduce the memory used by documents with many repeated keys or strings
+#[derive(Debug, Default)]
+pub struct Interner {
+    texts: RefCell<HashSet<Rc<str>>>,
+    bytes: RefCell<HashSet<Rc<[u8]>>>,
+}
+
+impl Interner {
+    /// Create a new empty interner
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the interned text equal to `s`, adding it to the interner if not present
+    pub fn intern_str(&self, s: &str) -> Rc<str> {
+        let mut texts = self.texts.borrow_mut();
+        if let Some(rc) = texts.get(s) {
+            return rc.clone();
+        }
+        let rc: Rc<str> = Rc::from(s);
+        texts.insert(rc.clone());
+        rc
+    }
+
+    /// Get the interned bytes equal to `b`, adding them to the interner if not present
+    pub fn intern_bytes(&self, b: &[u8]) -> Rc<[u8]> {
+        let mut bytes = self.bytes.borrow_mut();
+        if let Some(rc) = bytes.get(b) {
+            return rc.clone();
+        }
+        let rc: Rc<[u8]> = Rc::from(b);
+        bytes.insert(rc.clone());
+        rc
+    }
+
+    /// Return the number of distinct texts and bytes interned
+    pub fn len(&self) -> usize {
+        self.texts.borrow().len() + self.bytes.borrow().len()
+    }
+
+    /// Return whether nothing has been interned yet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Reader, Writer};
+
+    #[test]
+    fn intern() {
+        let interner = Interner::new();
+        let a = interner.intern_str("key");
+        let b = interner.intern_str("key");
+        assert!(Rc::ptr_eq(&a, &b));
+        let c = interner.intern_bytes(b"key");
+        assert_eq!(&*c, b"key");
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn decode_interned() {
+        let mut writer = Writer::new();
+        writer.encode(&("name".to_string(), "name".to_string(), "other".to_string()));
+        let bytes = writer.finalize();
+
+        let interner = Interner::new();
+        let mut reader = Reader::with_interner(&bytes, &interner);
+        let v = crate::decode_vec::<Rc<str>>(&mut reader).unwrap();
+        assert_eq!(v.len(), 3);
+        assert_eq!(&*v[0], "name");
+        assert!(Rc::ptr_eq(&v[0], &v[1]));
+        assert!(!Rc::ptr_eq(&v[0], &v[2]));
+        assert_eq!(interner.len(), 2);
+
+        // same content decoded by another reader sharing the interner
+        let mut reader = Reader::with_interner(&[0x64, b'n', b'a', b'm', b'e'], &interner);
+        let name: Rc<str> = reader.decode().unwrap();
+        assert!(Rc::ptr_eq(&name, &v[0]));
+
+        // without interner, every value is allocated
+        let v = crate::decode_vec::<Rc<str>>(&mut Reader::new(&bytes)).unwrap();
+        assert!(!Rc::ptr_eq(&v[0], &v[1]));
+    }
+
+    #[test]
+    fn decode_interned_nested() {
+        let bytes = crate::encode_to_bytes(&(("k".to_string(), "k".to_string()), "k".to_string()));
+
+        let interner = Interner::new();
+        let mut reader = Reader::with_interner(&bytes, &interner);
+        let ((a, b), c): ((Rc<str>, Rc<str>), Rc<str>) = reader.decode().unwrap();
+        assert!(Rc::ptr_eq(&a, &b));
+        assert!(Rc::ptr_eq(&a, &c));
+        assert_eq!(interner.len(), 1);
+
+        let mut reader = Reader::with_interner(&bytes, &interner);
+        let ((d, _), _) = reader
+            .array_tuple::<((Rc<str>, Rc<str>), Rc<str>)>()
+            .unwrap();
+        assert!(Rc::ptr_eq(&a, &d));
+        assert_eq!(interner.len(), 1);
+
+        // the content of a byte string holding CBOR is decoded with the same interner
+        let mut reader = Reader::with_interner(&[0x42, 0x61, b'k'], &interner);
+        let e: Rc<str> = reader.nested_cbor().unwrap();
+        assert!(Rc::ptr_eq(&a, &e));
+        assert_eq!(interner.len(), 1);
+    }
+
+    #[test]
+    fn encode_interned() {
+        let interner = Interner::new();
+        let text = interner.intern_str("key");
+        let bytes = interner.intern_bytes(&[1, 2]);
+        assert_eq!(crate::encode_to_bytes(&text), [0x63, b'k', b'e', b'y']);
+        assert_eq!(crate::encode_to_bytes(&bytes), [0x42, 0x01, 0x02]);
+    }
+}
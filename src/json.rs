@@ -0,0 +1,179 @@
+//! Conversion of CBOR data to JSON values, for inspection and logging
+use super::prim::CborSlice;
+use super::reader::{Reader, ReaderPolicy, MAX_RECURSION_DEPTH};
+use super::types::*;
+use serde_json::{Map as JsonMap, Number, Value};
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// standard base64 with padding
+fn base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | (b[2] as u32);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// a nested element that cannot be read (e.g. invalid UTF8 text, or nesting more
+// than `depth` levels) is mapped to a `{ "invalid": base64 }` object of its raw CBOR bytes
+fn slice_to_json(slice: &CborSlice, depth: usize) -> Value {
+    let policy = ReaderPolicy {
+        max_depth: depth,
+        ..ReaderPolicy::default()
+    };
+    let mut reader = Reader::with_policy(slice.as_ref(), policy);
+    let data = reader
+        .data()
+        .and_then(|data| reader.expect_finished().map(|()| data));
+    match data {
+        Ok(data) => data_to_json(&data, depth),
+        Err(_) => {
+            let mut object = JsonMap::new();
+            object.insert("invalid".to_string(), Value::String(base64(slice.as_ref())));
+            Value::Object(object)
+        }
+    }
+}
+
+// convert an element checked to nest at most `depth` levels
+fn data_to_json(data: &Data, depth: usize) -> Value {
+    let depth = depth.saturating_sub(1);
+    match data {
+        Data::Positive(v) => Value::Number(Number::from(v.to_u64())),
+        Data::Negative(v) => match v.to_i64() {
+            Some(i) => Value::Number(Number::from(i)),
+            // out of i64 range, so lose some precision
+            None => float_to_json(-1.0 - v.negative_u64() as f64),
+        },
+        Data::Float(v) => float_to_json(v.to_f64()),
+        Data::Byte(v) => simple_to_json(v.to_u8()),
+        Data::Simple(v) => simple_to_json(*v),
+        Data::Bytes(v) => Value::String(base64(&v.to_vec())),
+        Data::Text(v) => Value::String(v.to_string()),
+        Data::Array(v) => {
+            Value::Array(v.elements.iter().map(|s| slice_to_json(s, depth)).collect())
+        }
+        Data::Map(v) => {
+            let pairs = v
+                .elements
+                .iter()
+                .map(|(k, v)| (slice_to_json(k, depth), slice_to_json(v, depth)))
+                .collect::<Vec<_>>();
+            // only use a JSON object when all keys are text
+            if pairs.iter().all(|(k, _)| k.is_string()) {
+                let mut object = JsonMap::new();
+                for (k, v) in pairs {
+                    if let Value::String(k) = k {
+                        object.insert(k, v);
+                    }
+                }
+                Value::Object(object)
+            } else {
+                Value::Array(
+                    pairs
+                        .into_iter()
+                        .map(|(k, v)| Value::Array(vec![k, v]))
+                        .collect(),
+                )
+            }
+        }
+        Data::Tag(v) => {
+            let mut object = JsonMap::new();
+            object.insert("tag".to_string(), Value::Number(Number::from(v.value())));
+            object.insert("value".to_string(), slice_to_json(v.data(), depth));
+            Value::Object(object)
+        }
+        Data::True => Value::Bool(true),
+        Data::False => Value::Bool(false),
+        Data::Null | Data::Undefined => Value::Null,
+    }
+}
+
+// NaN and infinites cannot be represented in JSON, and are mapped to null
+fn float_to_json(f: f64) -> Value {
+    Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+fn simple_to_json(v: u8) -> Value {
+    let mut object = JsonMap::new();
+    object.insert("simple".to_string(), Value::Number(Number::from(v)));
+    Value::Object(object)
+}
+
+impl DataOwned {
+    /// Convert the CBOR data to a JSON value
+    ///
+    /// * bytes are converted to base64 strings
+    /// * maps with only text keys are converted to objects, other maps to arrays of `[key, value]` pairs
+    /// * tags are converted to `{ "tag": N, "value": ... }` objects
+    /// * simple values are converted to `{ "simple": N }` objects
+    /// * undefined, and floats not representable in JSON (NaN, infinites) are converted to null
+    /// * nested elements that cannot be read (e.g. invalid UTF8 text) are converted to
+    ///   `{ "invalid": base64 }` objects of their raw CBOR bytes, as well as the elements
+    ///   nested in more than `MAX_RECURSION_DEPTH` arrays, maps and tags
+    pub fn to_json(&self) -> Value {
+        data_to_json(&self.borrow(), MAX_RECURSION_DEPTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_from_bytes;
+
+    #[test]
+    fn base64_padding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foob"), "Zm9vYg==");
+    }
+
+    #[test]
+    fn to_json() {
+        // [1, -2, h'666f', {"a": true}, {1: null}, 1(2.5)]
+        const DATA: &[u8] = &[
+            0x86, 0x01, 0x21, 0x42, 0x66, 0x6f, 0xa1, 0x61, 0x61, 0xf5, 0xa1, 0x01, 0xf6, 0xc1,
+            0xf9, 0x41, 0x00,
+        ];
+        let data: DataOwned = decode_from_bytes(DATA).expect("data");
+        assert_eq!(
+            data.to_json().to_string(),
+            r#"[1,-2,"Zm8=",{"a":true},[[1,null]],{"tag":1,"value":2.5}]"#
+        );
+
+        // [1, "\xff"] with an invalid UTF8 text
+        let data: DataOwned = decode_from_bytes(&[0x82, 0x01, 0x61, 0xff]).expect("data");
+        assert_eq!(data.to_json().to_string(), r#"[1,{"invalid":"Yf8="}]"#);
+
+        // elements nested too deep are converted to invalid objects
+        let nested = |depth: usize| {
+            let mut bytes = vec![0x81; depth];
+            bytes.push(0x01);
+            decode_from_bytes::<DataOwned>(&bytes).expect("data")
+        };
+        let json = nested(MAX_RECURSION_DEPTH).to_json().to_string();
+        assert!(json.contains("[[1]]") && !json.contains("invalid"));
+        for depth in [MAX_RECURSION_DEPTH + 1, 1_000_000] {
+            let json = nested(depth).to_json();
+            assert!(json.as_array().unwrap()[0].get("invalid").is_some());
+        }
+    }
+}
@@ -15,6 +15,9 @@
 //! })
 
 mod context;
+pub mod cose;
+mod diff;
+pub mod fuzz;
 mod prim;
 
 mod reader;
@@ -23,6 +26,23 @@ mod writer;
 mod decode;
 mod encode;
 
+mod event;
+mod resumable;
+
+#[cfg(feature = "json")]
+mod json;
+
+#[cfg(feature = "indexmap")]
+mod index_map;
+
+mod interner;
+
+#[cfg(feature = "digest")]
+mod hashing;
+
+#[cfg(feature = "arrayvec")]
+mod array_vec;
+
 pub mod tagged;
 
 mod lowlevel;
@@ -32,29 +52,119 @@ pub mod state;
 mod types;
 pub mod validate;
 
-pub use reader::{Reader, ReaderError};
-pub use writer::Writer;
+pub use reader::{DecodeIter, Reader, ReaderError, ReaderPolicy, MAX_RECURSION_DEPTH};
+pub use writer::{IntegerOutOfRange, Writer};
 
-pub use decode::{decode_vec, Decode, DecodeError, DecodeErrorKind};
+pub use decode::{
+    decode_vec, ArrayTuple, Decode, DecodeBorrowed, DecodeError, DecodeErrorKind, DecodeFn,
+};
+pub use diff::{diff, DiffEntry};
 pub use encode::{encode_vec, Encode};
+pub use event::Event;
+pub use lowlevel::lead::Major;
+pub use resumable::ResumableReader;
+
+#[cfg(feature = "digest")]
+pub use hashing::HashingWriter;
 
-pub use prim::{CborDataOf, CborSliceOf};
+pub use interner::Interner;
+
+pub use prim::{CborData, CborDataOf, CborSlice, CborSliceOf};
 pub use types::*;
 
 #[cfg(feature = "derive")]
 pub use cbored_derive::CborRepr;
 
+/// A `mapint` field key, explicit or from the field position, used by another field
+/// is rejected when deriving:
+///
+/// ```compile_fail
+/// #[derive(cbored_derive::CborRepr)]
+/// #[cborrepr(structure = "mapint")]
+/// pub struct S {
+///     #[cborrepr(mandatory, key = 1)]
+///     a: u64,
+///     #[cborrepr(mandatory)]
+///     b: u64,
+/// }
+/// ```
+///
+/// as well as an explicit key that is skipped:
+///
+/// ```compile_fail
+/// #[derive(cbored_derive::CborRepr)]
+/// #[cborrepr(structure = "mapint", skipkey = 3)]
+/// pub struct S {
+///     #[cborrepr(mandatory, key = 3)]
+///     a: u64,
+/// }
+/// ```
+#[cfg(doctest)]
+pub struct MapIntKeysCompileFail;
+
+/// A `maptext` field key, renamed or from the field name, used by another field
+/// is rejected when deriving:
+///
+/// ```compile_fail
+/// #[derive(cbored_derive::CborRepr)]
+/// #[cborrepr(structure = "maptext")]
+/// pub struct S {
+///     #[cborrepr(mandatory, rename = "b")]
+///     a: u64,
+///     #[cborrepr(mandatory)]
+///     b: u64,
+/// }
+/// ```
+///
+/// as well as two fields renamed to the same key:
+///
+/// ```compile_fail
+/// #[derive(cbored_derive::CborRepr)]
+/// #[cborrepr(structure = "maptext")]
+/// pub struct S {
+///     #[cborrepr(mandatory, rename = "c")]
+///     a: u64,
+///     #[cborrepr(mandatory, rename = "c")]
+///     b: u64,
+/// }
+/// ```
+#[cfg(doctest)]
+pub struct MapTextKeysCompileFail;
+
+/// A variant `rename` is rejected when deriving an enum not serialized with the variant names:
+///
+/// ```compile_fail
+/// #[derive(cbored_derive::CborRepr)]
+/// #[cborrepr(enumtype = "tagvariant")]
+/// pub enum E {
+///     #[cborrepr(rename = "a")]
+///     A(u64),
+/// }
+/// ```
+#[cfg(doctest)]
+pub struct RenameCompileFail;
+
 /// Try to decode bytes into T from its CBOR bytes representation
 pub fn decode_from_bytes<T: Decode>(slice: &[u8]) -> Result<T, DecodeError> {
     let mut reader = Reader::new(slice);
     let t = reader.decode()?;
     reader
         .expect_finished()
-        .map_err(DecodeErrorKind::ReaderError)
+        .map_err(DecodeErrorKind::not_finished)
         .map_err(|e| e.context::<T>())?;
     Ok(t)
 }
 
+/// Try to decode T from the start of bytes, ignoring any data following it
+///
+/// Return the decoded T and the number of bytes it consumed, which is where
+/// the next element (if any) starts in the bytes
+pub fn decode_prefix_from_bytes<T: Decode>(slice: &[u8]) -> Result<(T, usize), DecodeError> {
+    let mut reader = Reader::new(slice);
+    let t = reader.decode()?;
+    Ok((t, reader.consumed_bytes()))
+}
+
 /// Encode an encodable type T into its CBOR bytes representation
 pub fn encode_to_bytes<T: Encode>(t: &T) -> Vec<u8> {
     let mut writer = Writer::new();
@@ -114,19 +224,1837 @@ mod tests {
         assert_eq!(pos.to_u64(), 124);
     }
 
+    #[test]
+    fn raw_slice() {
+        const DATA: &[u8] = &[0x82, 0x01, 0x9f, 0x02, 0xff, 0x18, 0x20];
+
+        let mut r = Reader::new(DATA);
+        let slice = r.raw_slice().expect("array slice");
+        assert_eq!(slice.as_ref(), &DATA[0..5]);
+        let slice = r.raw_slice().expect("positive slice");
+        assert_eq!(slice.as_ref(), &DATA[5..7]);
+        assert!(r.is_finished());
+    }
+
+    #[test]
+    fn tag_read_contents() {
+        // tag 1 containing [1, 2]
+        const DATA: &[u8] = &[0xc1, 0x82, 0x01, 0x02];
+
+        let mut r = Reader::new(DATA);
+        let tag = r.tag().expect("tag");
+        let (a, b) = tag
+            .read_contents(|reader| {
+                let array = reader
+                    .array()
+                    .map_err(DecodeErrorKind::ReaderError)
+                    .map_err(|e| e.context::<Array>())?;
+                let a: u64 = array[0].decode()?;
+                let b: u64 = array[1].decode()?;
+                Ok((a, b))
+            })
+            .expect("tag contents");
+        assert_eq!((a, b), (1, 2));
+
+        // not consuming the tagged element is an error
+        assert!(tag.read_contents(|reader| Ok(reader.peek_type())).is_err());
+    }
+
+    #[test]
+    fn simple_value() {
+        const DATA: &[u8] = &[0xf8, 0xff, 0xf8, 0x10, 0xf0];
+
+        let mut r = Reader::new(DATA);
+        assert_eq!(r.peek_type().unwrap(), Type::Simple);
+        let d1 = r.data().expect("simple");
+        assert_eq!(d1, Data::Simple(255));
+        assert_eq!(r.peek_type().unwrap(), Type::Byte);
+        let d2 = r.data().expect("non canonical byte");
+        assert_eq!(d2, Data::Byte(Byte(header::HeaderValue8::U8(0x10))));
+        let d3 = r.data().expect("byte");
+        assert_eq!(d3, Data::Byte(Byte::canonical(0x10)));
+        assert!(r.is_finished());
+
+        let mut writer = Writer::new();
+        writer.data(&d1);
+        writer.data(&d2);
+        writer.data(&d3);
+        assert_eq!(writer.finalize(), DATA);
+
+        // simple values below 32 use the same encoding as a Byte or a constant
+        let mut writer = Writer::new();
+        writer.data(&Data::Simple(5));
+        writer.data(&Data::Simple(0x15));
+        writer.data(&Data::Simple(0x1a));
+        assert_eq!(writer.finalize(), [0xe5, 0xf5, 0xf8, 0x1a]);
+
+        // bytes from 32 would be read back as simple values
+        assert!(std::panic::catch_unwind(|| Byte::canonical(200)).is_err());
+        assert_eq!(
+            Reader::new(&encode_to_bytes(&Data::Byte(Byte::canonical(0x13)).owned()))
+                .byte()
+                .unwrap(),
+            Byte::canonical(0x13)
+        );
+
+        // the type of a truncated one byte extension is still known
+        assert_eq!(Reader::new(&[0xf8]).peek_type().unwrap(), Type::Byte);
+    }
+
+    #[test]
+    fn encoded_cbor() {
+        let encoded = tagged::EncodedCBOR::from_encodable(&1000u64);
+        let bytes = encode_to_bytes(&encoded);
+        assert_eq!(bytes, [0xd8, 0x18, 0x43, 0x19, 0x03, 0xe8]);
+
+        let decoded: tagged::EncodedCBOR = decode_from_bytes(&bytes).expect("encoded cbor");
+        assert_eq!(decoded.decode_inner::<u64>().expect("inner u64"), 1000);
+        assert!(decoded.decode_inner::<String>().is_err());
+    }
+
+    #[test]
+    fn collect_remaining() {
+        const DATA: &[u8] = &[0x01, 0x61, 0x61, 0x80, 0xf6];
+
+        let mut r = Reader::new(DATA);
+        let items = r.collect_remaining().expect("cbor sequence");
+        assert_eq!(items.len(), 4);
+        assert_eq!(items[0], DataOwned::Positive(Positive::canonical(1)));
+        assert_eq!(items[3], DataOwned::Null);
+        assert!(r.is_finished());
+
+        // truncated last item
+        let mut r = Reader::new(&DATA[0..2]);
+        assert!(r.collect_remaining().is_err());
+    }
+
+    #[test]
+    fn write_integer() {
+        let mut writer = Writer::new();
+        writer.integer(10).unwrap();
+        writer.integer(-1).unwrap();
+        writer.integer(-500).unwrap();
+        writer.integer(u64::MAX as i128).unwrap();
+        writer.integer(-1 - u64::MAX as i128).unwrap();
+        assert_eq!(
+            writer.integer(u64::MAX as i128 + 1),
+            Err(IntegerOutOfRange(u64::MAX as i128 + 1))
+        );
+        assert!(writer.integer(-2 - u64::MAX as i128).is_err());
+        let cbor = writer.finalize();
+
+        let mut expected = vec![0x0a, 0x20, 0x39, 0x01, 0xf3, 0x1b];
+        expected.extend_from_slice(&[0xff; 8]);
+        expected.push(0x3b);
+        expected.extend_from_slice(&[0xff; 8]);
+        assert_eq!(cbor, expected);
+    }
+
+    #[test]
+    fn ip_addr() {
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+        let v4 = Ipv4Addr::new(192, 168, 0, 1);
+        let bytes = encode_to_bytes(&v4);
+        assert_eq!(bytes, [0x44, 192, 168, 0, 1]);
+        assert_eq!(decode_from_bytes::<Ipv4Addr>(&bytes).unwrap(), v4);
+        assert_eq!(decode_from_bytes::<IpAddr>(&bytes).unwrap(), IpAddr::V4(v4));
+        assert!(decode_from_bytes::<Ipv6Addr>(&bytes).is_err());
+
+        let v6 = Ipv6Addr::LOCALHOST;
+        let bytes = encode_to_bytes(&IpAddr::V6(v6));
+        assert_eq!(bytes.len(), 17);
+        assert_eq!(decode_from_bytes::<Ipv6Addr>(&bytes).unwrap(), v6);
+        assert_eq!(decode_from_bytes::<IpAddr>(&bytes).unwrap(), IpAddr::V6(v6));
+
+        assert!(decode_from_bytes::<IpAddr>(&[0x43, 1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn decode_any_of() {
+        #[derive(Debug, PartialEq, Eq)]
+        enum IntOrText {
+            Int(u64),
+            Text(String),
+        }
+        fn int(reader: &mut Reader) -> Result<IntOrText, DecodeError> {
+            reader.decode().map(IntOrText::Int)
+        }
+        fn text(reader: &mut Reader) -> Result<IntOrText, DecodeError> {
+            reader.decode().map(IntOrText::Text)
+        }
+
+        const DATA: &[u8] = &[0x61, 0x61, 0x05, 0xf6];
+        let mut r = Reader::new(DATA);
+        let v = r.decode_any_of(&[int, text]).expect("text");
+        assert_eq!(v, IntOrText::Text("a".to_string()));
+        let v = r.decode_any_of(&[int, text]).expect("int");
+        assert_eq!(v, IntOrText::Int(5));
+
+        let e = r.decode_any_of(&[int, text]).expect_err("null");
+        match e.error() {
+            DecodeErrorKind::NoMatchingAlternative(errors) => assert_eq!(errors.len(), 2),
+            _ => panic!("unexpected error {:?}", e),
+        }
+        // nothing consumed on failure
+        assert_eq!(r.consumed_bytes(), 3);
+
+        // the allocation budget used by a failed attempt is refunded
+        fn two_texts(reader: &mut Reader) -> Result<(String, String), DecodeError> {
+            reader.decode()
+        }
+        fn text_int(reader: &mut Reader) -> Result<(String, String), DecodeError> {
+            let (s, i): (String, u64) = reader.decode()?;
+            Ok((s, i.to_string()))
+        }
+        // ["a", 1]
+        const PAIR: &[u8] = &[0x82, 0x61, 0x61, 0x01];
+        let cost = 3 * std::mem::size_of::<&CborSlice>() + 1;
+        let mut r = Reader::with_alloc_budget(PAIR, cost);
+        let v = r
+            .decode_any_of(&[two_texts, text_int])
+            .expect("text and int");
+        assert_eq!(v, ("a".to_string(), "1".to_string()));
+    }
+
+    #[test]
+    fn events() {
+        // [1, h'01', (_ "a", "b"), {_ 1: null}]
+        const DATA: &[u8] = &[
+            0x84, 0x01, 0x41, 0x01, 0x7f, 0x61, 0x61, 0x61, 0x62, 0xff, 0xbf, 0x01, 0xf6, 0xff,
+        ];
+
+        let mut r = Reader::new(DATA);
+        let mut events = Vec::new();
+        while !r.is_finished() {
+            events.push(r.next_event().expect("event"));
+        }
+        assert_eq!(
+            events,
+            vec![
+                Event::ArrayStart(StructureLength::from(4)),
+                Event::Positive(Positive::canonical(1)),
+                Event::Bytes(BytesData::from_slice(&[0x01])),
+                Event::TextStart,
+                Event::Text(TextData::from_str("a")),
+                Event::Text(TextData::from_str("b")),
+                Event::Break,
+                Event::MapStart(StructureLength::Indefinite),
+                Event::Positive(Positive::canonical(1)),
+                Event::Null,
+                Event::Break,
+            ]
+        );
+
+        // break in a definite array
+        let mut r = Reader::new(&[0x81, 0xff]);
+        r.next_event().expect("array start");
+        assert!(matches!(r.next_event(), Err(ReaderError::StateError(_))));
+
+        // integer in an indefinite text
+        let mut r = Reader::new(&[0x7f, 0x01, 0xff]);
+        r.next_event().expect("text start");
+        assert!(r.next_event().is_err());
+    }
+
+    #[test]
+    fn expect_constant() {
+        let mut r = Reader::new(&[0xf5, 0xf6]);
+        r.expect_constant(Constant::True).unwrap();
+        match r.expect_constant(Constant::Undefined) {
+            Err(ReaderError::WrongExpectedType {
+                expected: Type::Undefined,
+                got: Type::Null,
+            }) => {}
+            e => panic!("unexpected result {:?}", e),
+        }
+        r.expect_constant(Constant::Null).unwrap();
+        assert!(r.is_finished());
+    }
+
+    #[test]
+    fn streamable_content_eq() {
+        // (_ h'0102', h'03')
+        let mut r = Reader::new(&[0x5f, 0x42, 0x01, 0x02, 0x41, 0x03, 0xff]);
+        let bytes = r.bytes().unwrap();
+        assert!(bytes == [1, 2, 3][..]);
+        assert!(bytes != [1, 2][..]);
+        assert!(bytes != [1, 2, 3, 4][..]);
+        assert!(bytes.owned() == [1, 2, 3][..]);
+
+        // (_ "ab", "c")
+        let mut r = Reader::new(&[0x7f, 0x62, 0x61, 0x62, 0x61, 0x63, 0xff]);
+        let text = r.text().unwrap();
+        assert!(text == *"abc");
+        assert!(text != *"ab");
+        assert!(text.owned() == *"abc");
+        assert!(Text::from_str("xyz") == *"xyz");
+    }
+
+    #[test]
+    fn decimal_fraction() {
+        // 4([-2, 27315]) from RFC8949
+        const DATA: &[u8] = &[0xc4, 0x82, 0x21, 0x19, 0x6a, 0xb3];
+        let decimal: tagged::DecimalFraction = decode_from_bytes(DATA).expect("decimal");
+        assert_eq!(decimal.to_f64(), 273.15);
+        let decimal = tagged::DecimalFraction::from_parts(27315, -2);
+        assert_eq!(encode_to_bytes(&decimal), DATA);
+
+        let decimal = tagged::DecimalFraction::from_parts(-(1 << 80), 0);
+        let bytes = encode_to_bytes(&decimal);
+        assert_eq!(&bytes[..5], &[0xc4, 0x82, 0x00, 0xc3, 0x4a]);
+        let decoded: tagged::DecimalFraction = decode_from_bytes(&bytes).expect("decimal");
+        assert_eq!(decoded, decimal);
+        assert_eq!(decoded.to_f64(), -((1u128 << 80) as f64));
+
+        assert_eq!(
+            tagged::DecimalFraction::from_parts(1, 400).to_f64(),
+            f64::INFINITY
+        );
+        assert_eq!(
+            tagged::DecimalFraction::from_parts(-1, 400).to_f64(),
+            f64::NEG_INFINITY
+        );
+        assert_eq!(tagged::DecimalFraction::from_parts(0, 400).to_f64(), 0.0);
+        assert_eq!(tagged::DecimalFraction::from_parts(1, -400).to_f64(), 0.0);
+        // near and in the subnormal range
+        assert_eq!(
+            tagged::DecimalFraction::from_parts(1, -320).to_f64(),
+            1e-320
+        );
+        assert_eq!(
+            tagged::DecimalFraction::from_parts(12345, -310).to_f64(),
+            12345e-310
+        );
+        assert_eq!(
+            tagged::DecimalFraction::from_parts(-12345, -310).to_f64(),
+            -12345e-310
+        );
+        assert_eq!(
+            tagged::DecimalFraction::from_parts(-5, -324).to_f64(),
+            -5e-324
+        );
+        assert_eq!(
+            tagged::DecimalFraction::from_parts(1, -308).to_f64(),
+            1e-308
+        );
+    }
+
+    #[test]
+    fn decode_iter() {
+        const DATA: &[u8] = &[0x01, 0x02, 0x03];
+        let mut r = Reader::new(DATA);
+        let items = r.decode_iter::<u64>().collect::<Result<Vec<_>, _>>();
+        assert_eq!(items.unwrap(), [1, 2, 3]);
+        assert!(r.is_finished());
+
+        let mut r = Reader::new(DATA);
+        let items = r.decode_iter::<u64>().take(2).map(|v| v.unwrap());
+        assert_eq!(items.collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(r.decode::<u64>().unwrap(), 3);
+
+        // the iterator stops after the first error
+        let mut r = Reader::new(&[0x01, 0x61, 0x61, 0x02]);
+        let mut iter = r.decode_iter::<u64>();
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn text_validation() {
+        // "ab", "é"
+        const DATA: &[u8] = &[0x62, 0x61, 0x62, 0x62, 0xc3, 0xa9];
+        let mut r = Reader::new(DATA);
+        assert_eq!(r.ascii_text().unwrap(), "ab");
+        assert!(matches!(r.ascii_text(), Err(ReaderError::TextNotAscii)));
+
+        let mut r = Reader::new(DATA);
+        let lowercase = |s: &str| s.chars().all(|c| c.is_ascii_lowercase());
+        assert_eq!(r.text_validated(lowercase).unwrap(), "ab");
+        assert!(matches!(
+            r.text_validated(lowercase),
+            Err(ReaderError::TextValidationFailed)
+        ));
+    }
+
+    #[test]
+    fn scalar_from_header_value() {
+        let p = Positive::from_header_value(header::HeaderValue::U8(1));
+        assert!(!p.is_canonical());
+        assert_eq!(encode_to_bytes(&p), [0x18, 0x01]);
+        let n = Negative::from_header_value(header::HeaderValue::U16(0));
+        assert_eq!(encode_to_bytes(&n), [0x39, 0x00, 0x00]);
+        assert_eq!(n.to_i64(), Some(-1));
+    }
+
+    #[test]
+    fn depth_tracking() {
+        // [1, [2]], 3
+        const DATA: &[u8] = &[0x82, 0x01, 0x81, 0x02, 0x03];
+        let mut r = Reader::with_depth_tracking(DATA);
+        assert_eq!(r.current_depth(), 0);
+        let array = r.array().unwrap();
+        let mut inner = r.nested_reader(array[1]);
+        assert_eq!(inner.current_depth(), 1);
+        let inner_array = inner.array().unwrap();
+        assert_eq!(inner.nested_reader(inner_array[0]).current_depth(), 2);
+        assert_eq!(r.current_depth(), 0);
+
+        let mut r = Reader::with_depth_tracking(DATA);
+        let mut depths = Vec::new();
+        while !r.is_finished() {
+            r.next_event().unwrap();
+            depths.push(r.current_depth());
+        }
+        assert_eq!(depths, [1, 1, 2, 0, 0]);
+
+        // not tracking
+        let mut r = Reader::new(DATA);
+        let array = r.array().unwrap();
+        assert_eq!(r.nested_reader(array[1]).current_depth(), 0);
+    }
+
+    #[test]
+    fn magic() {
+        let mut w = Writer::new();
+        w.write_magic();
+        w.encode(&1u64);
+        let bytes = w.finalize();
+        assert_eq!(bytes, [0xd9, 0xd9, 0xf7, 0x01]);
+
+        let mut r = Reader::new(&bytes);
+        assert!(r.skip_magic().unwrap());
+        assert_eq!(r.decode::<u64>().unwrap(), 1);
+
+        let mut r = Reader::new(&bytes[3..]);
+        assert!(!r.skip_magic().unwrap());
+        assert_eq!(r.decode::<u64>().unwrap(), 1);
+    }
+
+    #[test]
+    fn bounded_extraction() {
+        // (_ h'0102', h'03'), (_ "ab", "c")
+        const DATA: &[u8] = &[
+            0x5f, 0x42, 0x01, 0x02, 0x41, 0x03, 0xff, 0x7f, 0x62, 0x61, 0x62, 0x61, 0x63, 0xff,
+        ];
+        let mut r = Reader::new(DATA);
+        let bytes = r.bytes().unwrap();
+        assert_eq!(bytes.to_vec_bounded(3).unwrap(), [1, 2, 3]);
+        assert!(matches!(
+            bytes.to_vec_bounded(2),
+            Err(ReaderError::TooLarge { max: 2, got: 3 })
+        ));
+        let text = r.text().unwrap();
+        assert_eq!(text.to_string_bounded(3).unwrap(), "abc");
+        assert!(matches!(
+            text.to_string_bounded(2),
+            Err(ReaderError::TooLarge { max: 2, got: 3 })
+        ));
+    }
+
+    #[test]
+    fn structure_get() {
+        // [1, 2], {3: 4}
+        const DATA: &[u8] = &[0x82, 0x01, 0x02, 0xa1, 0x03, 0x04];
+        let mut r = Reader::new(DATA);
+        let array = r.array().unwrap();
+        assert_eq!(array.get(1).unwrap().decode::<u64>().unwrap(), 2);
+        assert!(array.get(2).is_none());
+
+        let map = r.map().unwrap();
+        let (mut k, mut v) = map.get_entry(0).unwrap();
+        assert_eq!(k.decode::<u64>().unwrap(), 3);
+        assert_eq!(v.decode::<u64>().unwrap(), 4);
+        assert!(map.get_entry(1).is_none());
+    }
+
+    #[test]
+    fn duration() {
+        let d = std::time::Duration::new(3, 500);
+        let bytes = encode_to_bytes(&d);
+        assert_eq!(bytes, [0x82, 0x03, 0x19, 0x01, 0xf4]);
+        assert_eq!(decode_from_bytes::<std::time::Duration>(&bytes).unwrap(), d);
+
+        // nanoseconds overflowing a second
+        let bytes = [0x82, 0x03, 0x1a, 0x3b, 0x9a, 0xca, 0x00];
+        assert!(decode_from_bytes::<std::time::Duration>(&bytes).is_err());
+        assert!(decode_from_bytes::<std::time::Duration>(&[0x81, 0x03]).is_err());
+    }
+
+    #[test]
+    fn fixed_bytes() {
+        const DATA: &[u8] = &[0x43, 0x01, 0x02, 0x03];
+        let mut r = Reader::new(DATA);
+        assert_eq!(r.fixed_bytes::<3>().unwrap(), [1, 2, 3]);
+
+        let mut r = Reader::new(DATA);
+        assert!(matches!(
+            r.fixed_bytes::<4>(),
+            Err(ReaderError::WrongExpectedLength {
+                expected: 4,
+                got: 3
+            })
+        ));
+        // the element is not consumed on error
+        assert_eq!(r.consumed_bytes(), 0);
+        assert_eq!(r.fixed_bytes::<3>().unwrap(), [1, 2, 3]);
+
+        // (_ h'01', h'0203')
+        const CHUNKS: &[u8] = &[0x5f, 0x41, 0x01, 0x42, 0x02, 0x03, 0xff];
+        let mut r = Reader::new(CHUNKS);
+        assert!(r.fixed_bytes::<2>().is_err());
+        assert_eq!(r.consumed_bytes(), 0);
+        assert_eq!(r.fixed_bytes::<3>().unwrap(), [1, 2, 3]);
+        assert!(r.is_finished());
+    }
+
+    #[test]
+    fn float_exact_roundtrip() {
+        let mut w = Writer::new();
+        w.float(Float::from_f64_exact(-0.0));
+        assert_eq!(w.finalize(), [0xfb, 0x80, 0, 0, 0, 0, 0, 0, 0]);
+
+        // -0.0 in FP16, FP32, FP64, and a NaN with a payload in FP64
+        let cases: [&[u8]; 4] = [
+            &[0xf9, 0x80, 0x00],
+            &[0xfa, 0x80, 0x00, 0x00, 0x00],
+            &[0xfb, 0x80, 0, 0, 0, 0, 0, 0, 0],
+            &[0xfb, 0x7f, 0xf0, 0, 0, 0, 0, 0, 0x01],
+        ];
+        for bytes in cases {
+            let f = Reader::new(bytes).float().unwrap();
+            let mut w = Writer::new();
+            w.float(f);
+            assert_eq!(w.finalize(), bytes);
+            let exact = Float::from_f64_exact(f.to_f64());
+            assert_eq!(exact.to_f64().to_bits(), f.to_f64().to_bits());
+        }
+        assert!(Reader::new(cases[0])
+            .float()
+            .unwrap()
+            .to_f64()
+            .is_sign_negative());
+    }
+
+    #[test]
+    fn resumable_reader() {
+        // [1, "ab"], 2
+        const DATA: &[u8] = &[0x82, 0x01, 0x62, 0x61, 0x62, 0x02];
+        let mut r = ResumableReader::new();
+        assert!(matches!(r.next_event(), Ok(None)));
+
+        let mut events = Vec::new();
+        for byte in DATA {
+            r.feed(&[*byte]);
+            while let Some(event) = r.next_event().unwrap() {
+                events.push(format!("{:?}", event));
+            }
+        }
+        assert!(r.is_finished());
+        assert_eq!(events.len(), 4);
+        assert!(events[0].starts_with("ArrayStart"));
+        assert!(events[2].contains("\"ab\""));
+
+        // the state is kept across feeds, so a break outside an indefinite structure is an error
+        let mut r = ResumableReader::new();
+        r.feed(&[0x81]);
+        r.next_event().unwrap();
+        assert!(!r.is_finished());
+        r.feed(&[0xff]);
+        assert!(r.next_event().is_err());
+    }
+
+    #[test]
+    fn reader_policy() {
+        let strict = ReaderPolicy {
+            require_canonical_ints: true,
+            allow_indefinite: false,
+            reject_duplicate_keys: true,
+            max_depth: 2,
+        };
+        // non canonical 1
+        assert!(Reader::new(&[0x18, 0x01]).positive().is_ok());
+        let mut r = Reader::with_policy(&[0x18, 0x01], strict);
+        assert!(matches!(
+            r.positive(),
+            Err(ReaderError::NonCanonicalInteger)
+        ));
+        // also inside a structure
+        let mut r = Reader::with_policy(&[0x81, 0x18, 0x01], strict);
+        assert!(r.array().is_err());
+
+        let mut r = Reader::with_policy(&[0x9f, 0xff], strict);
+        assert!(matches!(
+            r.array(),
+            Err(ReaderError::IndefiniteNotAllowed { got: Type::Array })
+        ));
+
+        // {1: 2, 1: 3}
+        const DUP: &[u8] = &[0xa2, 0x01, 0x02, 0x01, 0x03];
+        assert!(Reader::new(DUP).map().is_ok());
+        let mut r = Reader::with_policy(DUP, strict);
+        assert!(matches!(
+            r.map(),
+            Err(ReaderError::DuplicateKey {
+                first: 0,
+                duplicate: 1
+            })
+        ));
+
+        // [[1]] is at the maximum depth, [[[1]]] is too deep
+        let mut r = Reader::with_policy(&[0x81, 0x81, 0x01], strict);
+        let array = r.array().unwrap();
+        assert!(r.nested_reader(array[0]).array().is_ok());
+        let mut r = Reader::with_policy(&[0x81, 0x81, 0x81, 0x01], strict);
+        assert!(matches!(
+            r.array(),
+            Err(ReaderError::MaxDepthExceeded { max: 2 })
+        ));
+        let mut r = Reader::with_policy(&[0x81, 0x81, 0x81, 0x01], strict);
+        r.next_event().unwrap();
+        r.next_event().unwrap();
+        assert!(r.next_event().is_err());
+
+        // the depth is also enforced when decoding, at any level of nesting
+        let mut r = Reader::with_policy(&[0x81, 0x81, 0x01], strict);
+        assert_eq!(r.decode::<((u64,),)>().unwrap(), ((1,),));
+        let mut r = Reader::with_policy(&[0x81, 0x81, 0x81, 0x01], strict);
+        assert!(r.decode::<(((u64,),),)>().is_err());
+        let mut r = Reader::with_policy(&[0x81, 0x81, 0x81, 0x01], strict);
+        assert!(r.decode::<DataOwned>().is_err());
+        // [0, [[1]]] is rejected when reading the outer array, not only its second element
+        let mut r = Reader::with_policy(&[0x82, 0x00, 0x81, 0x81, 0x01], strict);
+        assert!(matches!(
+            r.array(),
+            Err(ReaderError::MaxDepthExceeded { max: 2 })
+        ));
+    }
+
+    #[test]
+    fn deep_nesting() {
+        // [[[...[1]...]]], the slicing of a structure is linear in its nesting depth
+        const DEPTH: usize = 200_000;
+        let mut data = vec![0x81; DEPTH];
+        data.push(0x01);
+        let mut r = Reader::new(&data);
+        let array = r.array().unwrap();
+        assert_eq!(array.len(), 1);
+        assert!(r.is_finished());
+
+        let strict = ReaderPolicy {
+            max_depth: DEPTH - 1,
+            ..ReaderPolicy::default()
+        };
+        let mut r = Reader::with_policy(&data, strict);
+        assert!(matches!(
+            r.array(),
+            Err(ReaderError::MaxDepthExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn reader_policy_nested() {
+        let strict = ReaderPolicy {
+            reject_duplicate_keys: true,
+            ..ReaderPolicy::default()
+        };
+        // [{1: 2, 1: 3}]
+        const DUP: &[u8] = &[0x81, 0xa2, 0x01, 0x02, 0x01, 0x03];
+        assert!(Reader::new(DUP).decode::<(DataOwned,)>().is_ok());
+        assert!(Reader::with_policy(DUP, strict)
+            .decode::<(DataOwned,)>()
+            .is_err());
+        assert!(decode_vec::<DataOwned>(&mut Reader::with_policy(DUP, strict)).is_err());
+        assert!(Reader::with_policy(DUP, strict)
+            .array_tuple::<(DataOwned,)>()
+            .is_err());
+        // [0, {1: 2, 1: 3}]
+        const RES: &[u8] = &[0x82, 0x00, 0xa2, 0x01, 0x02, 0x01, 0x03];
+        assert!(Reader::new(RES).decode::<Result<DataOwned, u64>>().is_ok());
+        assert!(Reader::with_policy(RES, strict)
+            .decode::<Result<DataOwned, u64>>()
+            .is_err());
+    }
+
+    #[test]
+    fn bounded_bytes() {
+        let b = BoundedBytes::<4>::from_slice(&[1, 2]).unwrap();
+        assert_eq!(encode_to_bytes(&b), [0x42, 0x01, 0x02]);
+        assert!(BoundedBytes::<1>::from_slice(&[1, 2]).is_none());
+
+        let decoded: BoundedBytes<4> = decode_from_bytes(&[0x42, 0x01, 0x02]).unwrap();
+        assert_eq!(decoded, b);
+        assert_eq!(decoded.as_slice(), [1, 2]);
+        // (_ h'01', h'0203')
+        let chunked = [0x5f, 0x41, 0x01, 0x42, 0x02, 0x03, 0xff];
+        let decoded: BoundedBytes<3> = decode_from_bytes(&chunked).unwrap();
+        assert_eq!(decoded.as_slice(), [1, 2, 3]);
+        assert!(decode_from_bytes::<BoundedBytes<2>>(&chunked).is_err());
+    }
+
+    #[test]
+    fn map_to_indexed_vec() {
+        // {1: "a", 3: "b"}
+        let data = [0xa2, 0x01, 0x61, 0x61, 0x03, 0x61, 0x62];
+        let mut reader = Reader::new(&data);
+        let map = reader.map().unwrap();
+        let v = map.to_indexed_vec::<String>(4).unwrap();
+        assert_eq!(
+            v,
+            vec![None, Some("a".to_string()), None, Some("b".to_string()),]
+        );
+        assert!(map.to_indexed_vec::<String>(2).is_err());
+        // the Vec is sized from the keys present, not from the maximum key
+        assert_eq!(map.to_indexed_vec::<String>(usize::MAX).unwrap(), v);
+        assert_eq!(map.to_indexed_vec::<String>(usize::MAX - 1).unwrap(), v);
+        let map = Reader::new(&[0xa0]).map().unwrap();
+        assert!(map.to_indexed_vec::<String>(4).unwrap().is_empty());
+
+        // {1: "a", 1: "b"}
+        let data = [0xa2, 0x01, 0x61, 0x61, 0x01, 0x61, 0x62];
+        let mut reader = Reader::new(&data);
+        let map = reader.map().unwrap();
+        assert!(map.to_indexed_vec::<String>(4).is_err());
+    }
+
+    #[test]
+    fn lenient_bool() {
+        // [true, false, 1, 0, 2, "a"]
+        let data = [0x86, 0xf5, 0xf4, 0x01, 0x00, 0x02, 0x61, 0x61];
+        let mut reader = Reader::new(&data);
+        let array = reader.array().unwrap();
+        let mut it = array.iter();
+        assert!(it.next().unwrap().lenient_bool().unwrap());
+        assert!(!it.next().unwrap().lenient_bool().unwrap());
+        assert!(it.next().unwrap().lenient_bool().unwrap());
+        assert!(!it.next().unwrap().lenient_bool().unwrap());
+        assert!(matches!(
+            it.next().unwrap().lenient_bool(),
+            Err(ReaderError::IntegerNotBoolean { got: 2 })
+        ));
+        assert!(it.next().unwrap().lenient_bool().is_err());
+    }
+
+    #[test]
+    fn debug_walk() {
+        // 1, [2, 3], "ab", then a truncated text
+        let data = [0x01, 0x82, 0x02, 0x03, 0x62, 0x61, 0x62, 0x63];
+        let mut reader = Reader::new(&data);
+        assert_eq!(
+            reader.debug_walk(),
+            vec![
+                (0, Type::Positive, 1),
+                (1, Type::Array, 3),
+                (4, Type::Text, 3)
+            ]
+        );
+        assert_eq!(reader.consumed_bytes(), 7);
+    }
+
+    #[test]
+    fn at_break() {
+        // content of [_ 1, 2] after the indefinite array header
+        let data = [0x01, 0x02, 0xff];
+        let mut reader = Reader::new(&data);
+        let mut values = Vec::new();
+        while !reader.at_break().unwrap() {
+            assert!(reader.consume_break().is_err());
+            values.push(reader.decode::<u64>().unwrap());
+        }
+        reader.consume_break().unwrap();
+        assert_eq!(values, [1, 2]);
+        assert!(reader.is_finished());
+    }
+
+    #[test]
+    fn result_encoding() {
+        let ok: Result<u64, String> = Ok(2);
+        assert_eq!(encode_to_bytes(&ok), [0x82, 0x00, 0x02]);
+        let err: Result<u64, String> = Err("e".to_string());
+        assert_eq!(encode_to_bytes(&err), [0x82, 0x01, 0x61, 0x65]);
+
+        assert_eq!(
+            decode_from_bytes::<Result<u64, String>>(&[0x82, 0x00, 0x02]).unwrap(),
+            ok
+        );
+        assert_eq!(
+            decode_from_bytes::<Result<u64, String>>(&[0x82, 0x01, 0x61, 0x65]).unwrap(),
+            err
+        );
+        assert!(decode_from_bytes::<Result<u64, String>>(&[0x82, 0x02, 0x02]).is_err());
+        assert!(decode_from_bytes::<Result<u64, String>>(&[0x81, 0x00]).is_err());
+    }
+
+    #[test]
+    fn map_sort() {
+        const KEY_10: &[u8] = &[0x0a];
+        const KEY_100: &[u8] = &[0x18, 0x64];
+        const KEY_M1: &[u8] = &[0x20];
+        const KEY_Z: &[u8] = &[0x61, 0x7a];
+        const KEY_AA: &[u8] = &[0x62, 0x61, 0x61];
+        const KEY_A100: &[u8] = &[0x81, 0x18, 0x64];
+        const KEY_AM1: &[u8] = &[0x81, 0x20];
+        const KEY_FALSE: &[u8] = &[0xf4];
+
+        let mut data = vec![0xa8];
+        for key in [
+            KEY_AA, KEY_FALSE, KEY_100, KEY_AM1, KEY_10, KEY_Z, KEY_A100, KEY_M1,
+        ] {
+            data.extend_from_slice(key);
+            data.push(0x00);
+        }
+        let map = Reader::new(&data).map().unwrap().owned();
+        let keys = |map: &MapOwned| {
+            map.elements
+                .iter()
+                .map(|(k, _)| k.as_ref().to_vec())
+                .collect::<Vec<_>>()
+        };
+
+        let mut sorted = map.clone();
+        sorted.sort_rfc8949();
+        assert_eq!(
+            keys(&sorted),
+            [KEY_10, KEY_100, KEY_M1, KEY_Z, KEY_AA, KEY_A100, KEY_AM1, KEY_FALSE]
+        );
+
+        let mut sorted = map;
+        sorted.sort_length_first();
+        assert_eq!(
+            keys(&sorted),
+            [KEY_10, KEY_M1, KEY_FALSE, KEY_100, KEY_Z, KEY_AM1, KEY_AA, KEY_A100]
+        );
+    }
+
+    #[test]
+    fn writer_raw_integers() {
+        let mut writer = Writer::new();
+        writer.array_build(StructureLength::from(3), |writer| {
+            writer.encode(&1u64);
+            writer.positive_raw(header::HeaderValue::U16(1));
+            writer.negative_raw(header::HeaderValue::U8(0));
+        });
+        let data = writer.finalize();
+        assert_eq!(data, [0x83, 0x01, 0x19, 0x00, 0x01, 0x38, 0x00]);
+
+        let mut reader = Reader::new(&data);
+        let array = reader.array().unwrap();
+        assert!(!array[1].reader().positive().unwrap().is_canonical());
+        assert!(!array[2].reader().negative().unwrap().is_canonical());
+    }
+
+    #[test]
+    fn tagged_generic() {
+        // 1234("a")
+        let bytes = [0xd9, 0x04, 0xd2, 0x61, 0x61];
+        let tagged: tagged::Tagged<String> = decode_from_bytes(&bytes).unwrap();
+        assert_eq!(tagged, tagged::Tagged::new(1234, "a".to_string()));
+        assert_eq!(encode_to_bytes(&tagged), bytes);
+
+        assert!(decode_from_bytes::<tagged::Tagged<u64>>(&bytes).is_err());
+        assert!(decode_from_bytes::<tagged::Tagged<u64>>(&[0x01]).is_err());
+    }
+
+    #[test]
+    fn text_cow() {
+        let mut reader = Reader::new(&[0x62, 0x61, 0x62]);
+        let text = reader.text_cow().unwrap();
+        assert!(matches!(text, std::borrow::Cow::Borrowed("ab")));
+
+        // (_ "a", "b")
+        let mut reader = Reader::new(&[0x7f, 0x61, 0x61, 0x61, 0x62, 0xff]);
+        let text = reader.text_cow().unwrap();
+        assert!(matches!(&text, std::borrow::Cow::Owned(s) if s == "ab"));
+        assert!(reader.is_finished());
+    }
+
+    #[test]
+    fn alloc_budget() {
+        let ref_size = std::mem::size_of::<&CborSlice>();
+        // ["ab", [1, 2]]
+        let data = [0x82, 0x62, 0x61, 0x62, 0x82, 0x01, 0x02];
+        // 5 elements and 2 bytes of text
+        let cost = 5 * ref_size + 2;
+
+        let mut reader = Reader::with_alloc_budget(&data, cost);
+        let array = reader.array().unwrap();
+        assert_eq!(array.len(), 2);
+
+        let mut reader = Reader::with_alloc_budget(&data, cost - 1);
+        assert!(matches!(
+            reader.array(),
+            Err(ReaderError::LimitExceeded { limit }) if limit == cost - 1
+        ));
+
+        // the budget is shared by all the reads
+        let mut reader = Reader::with_alloc_budget(&[0x61, 0x61, 0x61, 0x61], 2 * ref_size + 1);
+        reader.text().unwrap();
+        assert!(reader.text().is_err());
+    }
+
+    #[test]
+    fn writer_array_of_raw() {
+        let mut items = Vec::new();
+        for i in 0..2u64 {
+            let mut writer = Writer::new();
+            writer.encode(&i);
+            items.push(writer.finalize_data());
+        }
+        let mut writer = Writer::new();
+        writer.array_of_raw(&items);
+        assert_eq!(writer.finalize(), [0x82, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn diff_data() {
+        // {1: [1, 2], 3: "a", 4: 0}
+        let a = [0xa3, 0x01, 0x82, 0x01, 0x02, 0x03, 0x61, 0x61, 0x04, 0x00];
+        // {3: "a", 1: [1, 5, 6], 5: 0}
+        let b = [
+            0xa3, 0x03, 0x61, 0x61, 0x01, 0x83, 0x01, 0x05, 0x06, 0x05, 0x00,
+        ];
+        let entries = diff(&a, &b).unwrap();
+        let descriptions = entries.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            descriptions,
+            [
+                "root->map[key 1]->array[1]: 2 vs 5",
+                "root->map[key 1]->array[2]: <missing> vs 6",
+                "root->map[key 4]: 0 vs <missing>",
+                "root->map[key 5]: <missing> vs 0",
+            ]
+        );
+        assert!(diff(&a, &a).unwrap().is_empty());
+        assert!(diff(&a, &[0xa3]).is_err());
+
+        // the nesting depth of the compared elements is limited
+        let nested = |depth: usize, v: u8| {
+            let mut bytes = vec![0x81; depth];
+            bytes.push(v);
+            bytes
+        };
+        let entries = diff(
+            &nested(MAX_RECURSION_DEPTH, 1),
+            &nested(MAX_RECURSION_DEPTH, 2),
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path.len(), MAX_RECURSION_DEPTH + 1);
+        let deep = nested(1_000_000, 1);
+        assert!(diff(&deep, &deep).is_err());
+    }
+
+    #[test]
+    fn decode_field() {
+        // [1, "a"]
+        let data = [0x82, 0x01, 0x61, 0x61];
+        let mut reader = Reader::new(&data);
+        let array = reader.array().unwrap();
+        assert_eq!(array[0].decode_field::<u64>("id").unwrap(), 1);
+        let err = array[1].decode_field::<u64>("count").unwrap_err();
+        assert!(err.to_string().contains("count"));
+    }
+
+    #[test]
+    fn writer_header() {
+        let mut writer = Writer::new();
+        writer.write_header(Major::Array, header::HeaderValue::U8(1));
+        writer.write_header(Major::Bytes, header::HeaderValue::Imm(0));
+        let data = writer.finalize();
+        assert_eq!(data, [0x98, 0x01, 0x40]);
+        let array = Reader::new(&data).array().unwrap();
+        assert_eq!(array.len(), 1);
+    }
+
+    #[test]
+    fn list_roundtrip() {
+        for data in [
+            &[0x82, 0x01, 0x02][..],
+            &[0x9f, 0x01, 0x02, 0xff][..],
+            &[0x98, 0x02, 0x01, 0x02][..],
+        ] {
+            let list: List<u64> = decode_from_bytes(data).unwrap();
+            assert_eq!(list.as_slice(), [1, 2]);
+            assert_eq!(encode_to_bytes(&list), data);
+        }
+
+        let mut list: List<u64> = decode_from_bytes(&[0x98, 0x02, 0x01, 0x02]).unwrap();
+        list.elements_mut().push(3);
+        assert_eq!(encode_to_bytes(&list), [0x83, 0x01, 0x02, 0x03]);
+        let list = List::indefinite(vec![1u64]);
+        assert_eq!(encode_to_bytes(&list), [0x9f, 0x01, 0xff]);
+    }
+
+    #[test]
+    fn decode_error_incomplete() {
+        // truncated array of 2 elements
+        let err = decode_from_bytes::<List<u64>>(&[0x82, 0x01]).unwrap_err();
+        assert!(err.is_incomplete());
+        // malformed lead byte
+        let err = decode_from_bytes::<u64>(&[0x1c]).unwrap_err();
+        assert!(!err.is_incomplete());
+        let err = decode_from_bytes::<u64>(&[0x61, 0x61]).unwrap_err();
+        assert!(!err.is_incomplete());
+    }
+
+    #[test]
+    fn try_peek_type() {
+        let mut r = Reader::new(&[0x01, 0xf8]);
+        assert_eq!(r.try_peek_type().unwrap(), Some(Type::Positive));
+        let _: u64 = r.decode().unwrap();
+        // truncated one byte extension, the lead byte is enough to know the type
+        assert_eq!(r.try_peek_type().unwrap(), Some(Type::Byte));
+        assert!(r.byte().is_err());
+
+        let mut r = Reader::new(&[0x01]);
+        let _: u64 = r.decode().unwrap();
+        assert_eq!(r.try_peek_type().unwrap(), None);
+
+        let r = Reader::new(&[0x1c]);
+        assert!(r.try_peek_type().is_err());
+    }
+
+    #[test]
+    fn data_recursive() {
+        // 1([2, {3: "a"}])
+        const DATA: &[u8] = &[0xc1, 0x82, 0x02, 0xa1, 0x03, 0x61, 0x61];
+        let mut r = Reader::new(DATA);
+        let tree = r.data_recursive().unwrap();
+        assert!(r.is_finished());
+        let text = Reader::new(&DATA[5..]).text().unwrap();
+        assert_eq!(
+            tree,
+            DataTree::Tag(
+                TagValue::from_u64(1),
+                Box::new(DataTree::Array(
+                    StructureLength::from(2),
+                    vec![
+                        DataTree::Positive(Positive::canonical(2)),
+                        DataTree::Map(
+                            StructureLength::from(1),
+                            vec![(
+                                DataTree::Positive(Positive::canonical(3)),
+                                DataTree::Text(text)
+                            )]
+                        ),
+                    ]
+                ))
+            )
+        );
+
+        // depth limit applies to the nested elements
+        let policy = ReaderPolicy {
+            max_depth: 2,
+            ..ReaderPolicy::default()
+        };
+        let mut r = Reader::with_policy(DATA, policy);
+        assert!(matches!(
+            r.data_recursive(),
+            Err(ReaderError::MaxDepthExceeded { max: 2 })
+        ));
+
+        // without policy, the nesting is still limited
+        let nested = |depth: usize| {
+            let mut data = vec![0x81; depth];
+            data.push(0x01);
+            data
+        };
+        let data = nested(MAX_RECURSION_DEPTH);
+        assert!(Reader::new(&data).data_recursive().is_ok());
+        let data = nested(MAX_RECURSION_DEPTH + 1);
+        assert!(matches!(
+            Reader::new(&data).data_recursive(),
+            Err(ReaderError::MaxDepthExceeded {
+                max: MAX_RECURSION_DEPTH
+            })
+        ));
+        let data = nested(1_000_000);
+        assert!(Reader::new(&data).data_recursive().is_err());
+        // the reader settings are restored afterwards
+        let mut r = Reader::new(&[0x81, 0x01, 0x01]);
+        assert!(r.data_recursive().is_ok());
+        assert_eq!(r.current_depth(), 0);
+        assert_eq!(r.policy(), ReaderPolicy::default());
+    }
+
+    #[test]
+    fn map_decode_entry() {
+        // {1: "a", 2: 3}
+        let mut r = Reader::new(&[0xa2, 0x01, 0x61, 0x61, 0x02, 0x03]);
+        let map = r.map().unwrap();
+        let (k, v): (u64, String) = map.decode_entry(0).unwrap();
+        assert_eq!((k, v.as_str()), (1, "a"));
+        let (k, v): (u64, u64) = map.decode_entry(1).unwrap();
+        assert_eq!((k, v), (2, 3));
+        let err = map.decode_entry::<u64, u64>(0).unwrap_err();
+        assert!(format!("{}", err).contains("value"));
+        assert!(map.decode_entry::<u64, u64>(2).is_err());
+    }
+
+    #[test]
+    fn value() {
+        let v = Value::map(vec![
+            (Value::uint(1), Value::text("a")),
+            (
+                Value::int(-2),
+                Value::array(vec![Value::from(true), Value::Null]),
+            ),
+            (Value::from("b"), Value::tag(24, Value::bytes(&[1, 2]))),
+        ]);
+        let bytes = encode_to_bytes(&v);
+        assert_eq!(
+            bytes,
+            [
+                0xa3, 0x01, 0x61, 0x61, 0x21, 0x82, 0xf5, 0xf6, 0x61, 0x62, 0xd8, 0x18, 0x42, 0x01,
+                0x02
+            ]
+        );
+        let decoded: Value = decode_from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, v);
+        assert_eq!(
+            decoded
+                .get(&Value::int(-2))
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(Value::int(-2).as_i64(), Some(-2));
+        assert_eq!(Value::uint(u64::MAX).as_i64(), None);
+
+        // encoding details are not kept
+        let decoded: Value =
+            decode_from_bytes(&[0x9f, 0x18, 0x01, 0xf9, 0x3c, 0x00, 0xff]).unwrap();
+        assert_eq!(
+            decoded,
+            Value::array(vec![Value::uint(1), Value::Float(1.0)])
+        );
+        for simple in [0x10, 0x20, 0xff] {
+            let v = Value::simple(simple).unwrap();
+            assert_eq!(decode_from_bytes::<Value>(&encode_to_bytes(&v)).unwrap(), v);
+        }
+        // simple values 20 to 31 in the two bytes form are not well-formed
+        assert!(decode_from_bytes::<Value>(&[0xf8, 0x18]).is_err());
+        assert!(decode_from_bytes::<Value>(&[0x81, 0xf8, 0x14]).is_err());
+        // the values 20 to 23 are the constants, 24 to 31 cannot be created
+        assert_eq!(Value::simple(0x15), Some(Value::Bool(true)));
+        assert_eq!(Value::simple(0x17), Some(Value::Undefined));
+        assert_eq!(Value::simple(0x18), None);
+        assert_eq!(SimpleValue::new(0x1f), None);
+        assert_eq!(encode_to_bytes(&Value::simple(0x14).unwrap()), [0xf4]);
+
+        // the nesting is limited when decoding
+        let mut data = vec![0x81; MAX_RECURSION_DEPTH + 1];
+        data.push(0x01);
+        assert!(decode_from_bytes::<Value>(&data[1..]).is_ok());
+        assert!(decode_from_bytes::<Value>(&data).is_err());
+        let mut data = vec![0x81; 1_000_000];
+        data.push(0x01);
+        assert!(decode_from_bytes::<Value>(&data).is_err());
+    }
+
+    #[test]
+    fn nested_cbor() {
+        // h'8201f5' , (_ h'82', h'01f5')
+        let mut r = Reader::new(&[
+            0x43, 0x82, 0x01, 0xf5, 0x5f, 0x41, 0x82, 0x42, 0x01, 0xf5, 0xff,
+        ]);
+        for _ in 0..2 {
+            let v: Value = r.nested_cbor().unwrap();
+            assert_eq!(v, Value::array(vec![Value::uint(1), Value::Bool(true)]));
+        }
+        assert!(r.is_finished());
+
+        // trailing data inside the byte string
+        let mut r = Reader::new(&[0x42, 0x01, 0x02]);
+        assert!(r.nested_cbor::<u64>().is_err());
+        // empty byte string
+        let mut r = Reader::new(&[0x40]);
+        assert!(r.nested_cbor::<u64>().unwrap_err().is_incomplete());
+
+        // the content is charged to the allocation budget of the reader:
+        // the byte string with its 3 bytes, then the array and its 2 elements
+        let ref_size = std::mem::size_of::<&CborSlice>();
+        let data = [0x43, 0x82, 0x01, 0xf5, 0x43, 0x82, 0x01, 0xf5];
+        let mut r = Reader::with_alloc_budget(&data, 4 * ref_size + 3);
+        assert!(r.nested_cbor::<Value>().is_ok());
+        let mut r = Reader::with_alloc_budget(&data, 4 * ref_size + 2);
+        assert!(r.nested_cbor::<Value>().is_err());
+        // and the budget spent inside is not available anymore to the next reads
+        let mut r = Reader::with_alloc_budget(&data, 5 * ref_size + 6);
+        assert!(r.nested_cbor::<Value>().is_ok());
+        assert!(r.nested_cbor::<Value>().is_err());
+    }
+
+    #[test]
+    fn empty_input() {
+        let empty: Vec<u8> = Vec::new();
+        let mut r = Reader::from_vec(&empty);
+        assert!(r.is_finished());
+        assert!(r.expect_finished().is_ok());
+        assert_eq!(r.try_peek_type().unwrap(), None);
+        assert!(matches!(r.peek_type(), Err(ReaderError::DataMissing(_))));
+        assert!(matches!(r.data(), Err(ReaderError::DataMissing(_))));
+        assert!(r.collect_remaining().unwrap().is_empty());
+        assert!(r.debug_walk().is_empty());
+        assert!(r.decode_iter::<u64>().next().is_none());
+
+        assert!(decode_from_bytes::<u64>(&[]).unwrap_err().is_incomplete());
+        assert!(decode_from_bytes::<Value>(&empty)
+            .unwrap_err()
+            .is_incomplete());
+        assert!(CborData(Vec::new())
+            .decode::<u64>()
+            .unwrap_err()
+            .is_incomplete());
+    }
+
+    #[test]
+    fn trailing_data() {
+        let err = decode_from_bytes::<u64>(&[0x01, 0x02, 0x03]).unwrap_err();
+        assert!(matches!(
+            err.error(),
+            DecodeErrorKind::TrailingData {
+                at: 1,
+                remaining_bytes: 2
+            }
+        ));
+        let data = CborData(vec![0x80, 0x80]);
+        assert!(matches!(
+            data.decode::<List<u64>>().unwrap_err().error(),
+            DecodeErrorKind::TrailingData {
+                at: 1,
+                remaining_bytes: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn canonical_bytes_slice() {
+        let mut r = Reader::new(&[0x42, 0x01, 0x02]);
+        assert_eq!(r.canonical_bytes_slice(2).unwrap(), [1, 2]);
+        assert!(r.is_finished());
+
+        let mut r = Reader::new(&[0x42, 0x01, 0x02]);
+        assert!(matches!(
+            r.canonical_bytes_slice(3),
+            Err(ReaderError::WrongExpectedLength {
+                expected: 3,
+                got: 2
+            })
+        ));
+        // non canonical length header
+        let mut r = Reader::new(&[0x58, 0x02, 0x01, 0x02]);
+        assert!(matches!(
+            r.canonical_bytes_slice(2),
+            Err(ReaderError::NonCanonicalLength { got: Type::Bytes })
+        ));
+        let mut r = Reader::new(&[0x5f, 0x42, 0x01, 0x02, 0xff]);
+        assert!(matches!(
+            r.canonical_bytes_slice(2),
+            Err(ReaderError::ChunkedNotContiguous { got: Type::Bytes })
+        ));
+        let mut r = Reader::new(&[0x62, 0x61, 0x62]);
+        assert!(r.canonical_bytes_slice(2).is_err());
+        // the element is not consumed on error
+        assert_eq!(r.consumed_bytes(), 0);
+    }
+
+    #[test]
+    fn btreemap_sorted_by_encoded_key() {
+        use std::collections::BTreeMap;
+
+        // "aa" < "b" in Rust, but the encoding of "b" (0x61 0x62) is before "aa" (0x62 0x61 0x61)
+        let mut map = BTreeMap::new();
+        map.insert("aa".to_string(), 1u64);
+        map.insert("b".to_string(), 2u64);
+        let bytes = encode_to_bytes(&map);
+        assert_eq!(bytes, [0xa2, 0x61, 0x62, 0x02, 0x62, 0x61, 0x61, 0x01]);
+        assert_eq!(
+            decode_from_bytes::<BTreeMap<String, u64>>(&bytes).unwrap(),
+            map
+        );
+
+        assert!(decode_from_bytes::<BTreeMap<u64, u64>>(&[0xa2, 0x01, 0x01, 0x01, 0x02]).is_err());
+    }
+
+    #[test]
+    fn decode_error_bytes_needed() {
+        // bytes of length 4, with only 1 byte of content
+        let err = decode_from_bytes::<Vec<u8>>(&[0x44, 0x01]).unwrap_err();
+        assert_eq!(err.bytes_needed(), Some(3));
+        // missing the 2 bytes of the integer
+        let err = decode_from_bytes::<u64>(&[0x19]).unwrap_err();
+        assert_eq!(err.bytes_needed(), Some(2));
+        let err = decode_from_bytes::<u64>(&[]).unwrap_err();
+        assert_eq!(err.bytes_needed(), Some(1));
+        let err = decode_from_bytes::<u64>(&[0x61, 0x61]).unwrap_err();
+        assert_eq!(err.bytes_needed(), None);
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn ranges() {
+        for range in [1..10u64, 5..5, 7..2] {
+            let bytes = encode_to_bytes(&range);
+            assert_eq!(bytes, [0x82, range.start as u8, range.end as u8]);
+            assert_eq!(
+                decode_from_bytes::<std::ops::Range<u64>>(&bytes).unwrap(),
+                range
+            );
+        }
+        for range in [1..=10u64, 5..=5, 7..=2] {
+            let bytes = encode_to_bytes(&range);
+            assert_eq!(bytes, [0x82, *range.start() as u8, *range.end() as u8]);
+            let decoded: std::ops::RangeInclusive<u64> = decode_from_bytes(&bytes).unwrap();
+            assert_eq!(decoded, range);
+        }
+        assert!(decode_from_bytes::<std::ops::Range<u64>>(&[0x83, 0x01, 0x02, 0x03]).is_err());
+    }
+
+    #[test]
+    fn decode_with_raw() {
+        // non canonical 1, followed by [2]
+        let mut r = Reader::new(&[0x18, 0x01, 0x81, 0x02]);
+        let (v, raw): (u64, _) = r.decode_with_raw().unwrap();
+        assert_eq!((v, raw.as_ref()), (1, &[0x18, 0x01][..]));
+        let (v, raw): (List<u64>, _) = r.decode_with_raw().unwrap();
+        assert_eq!(
+            (v.as_slice(), raw.as_ref()),
+            (&[2u64][..], &[0x81, 0x02][..])
+        );
+        assert!(r.is_finished());
+    }
+
+    #[test]
+    fn definite_array_of_len() {
+        let mut r = Reader::new(&[0x82, 0x01, 0x02]);
+        assert_eq!(r.definite_array_of_len(2).unwrap().len(), 2);
+        assert!(r.is_finished());
+
+        let mut r = Reader::new(&[0x82, 0x01, 0x02]);
+        assert!(matches!(
+            r.definite_array_of_len(3),
+            Err(ReaderError::WrongExpectedLength {
+                expected: 3,
+                got: 2
+            })
+        ));
+        let mut r = Reader::new(&[0x9f, 0x01, 0x02, 0xff]);
+        assert!(matches!(
+            r.definite_array_of_len(2),
+            Err(ReaderError::IndefiniteNotAllowed { got: Type::Array })
+        ));
+        assert_eq!(r.consumed_bytes(), 0);
+        // the array_of_len counterpart accepts it
+        assert_eq!(r.array_of_len(2).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn map_builder_optional() {
+        let mut builder = MapBuilder::new();
+        builder.insert(&0u64, &"a".to_string());
+        builder.insert_optional(1, &None::<u64>);
+        builder.insert_optional(2, &Some(true));
+        assert_eq!(builder.len(), 2);
+        let map = builder.finite();
+        assert_eq!(encode_to_bytes(&map), [0xa2, 0x00, 0x61, 0x61, 0x02, 0xf5]);
+    }
+
+    #[test]
+    fn be_uint_from_bytes() {
+        let mut r = Reader::new(&[
+            0x43, 0x01, 0x02, 0x03, 0x40, 0x5f, 0x41, 0xff, 0x41, 0x01, 0xff,
+        ]);
+        assert_eq!(r.be_uint_from_bytes(3).unwrap(), 0x010203);
+        assert_eq!(r.be_uint_from_bytes(0).unwrap(), 0);
+        assert_eq!(r.be_uint_from_bytes(2).unwrap(), 0xff01);
+        assert!(r.is_finished());
+
+        let mut r = Reader::new(&[0x48, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe]);
+        assert!(matches!(
+            r.be_uint_from_bytes(9),
+            Err(ReaderError::TooLarge { max: 8, got: 9 })
+        ));
+        assert_eq!(r.be_uint_from_bytes(8).unwrap(), u64::MAX - 1);
+
+        let mut r = Reader::new(&[0x42, 0x01, 0x02]);
+        assert!(matches!(
+            r.be_uint_from_bytes(4),
+            Err(ReaderError::WrongExpectedLength {
+                expected: 4,
+                got: 2
+            })
+        ));
+        // the element is not consumed on error
+        assert_eq!(r.be_uint_from_bytes(2).unwrap(), 0x0102);
+    }
+
+    #[test]
+    fn tagged_expected_conversion() {
+        use tagged::{ExpectedBase16, ExpectedBase64, ExpectedBase64Url};
+
+        // 23(h'0102')
+        const DATA: &[u8] = &[0xd7, 0x42, 0x01, 0x02];
+        let v: ExpectedBase16 = decode_from_bytes(DATA).unwrap();
+        assert_eq!(v.decode_inner::<Vec<u8>>().unwrap(), [1, 2]);
+        assert_eq!(encode_to_bytes(&v), DATA);
+        assert!(decode_from_bytes::<ExpectedBase64>(DATA).is_err());
+
+        // any item is accepted, and kept as is
+        let v: ExpectedBase64Url = decode_from_bytes(&[0xd5, 0x82, 0x18, 0x01, 0x40]).unwrap();
+        assert_eq!(v.data().as_ref(), [0x82, 0x18, 0x01, 0x40]);
+        assert_eq!(encode_to_bytes(&v), [0xd5, 0x82, 0x18, 0x01, 0x40]);
+        assert_eq!(
+            encode_to_bytes(&ExpectedBase64::from_encodable(&1u64)),
+            [0xd6, 0x01]
+        );
+    }
+
+    #[test]
+    fn to_canonical() {
+        let p = Positive::from_header_value(header::HeaderValue::U32(1));
+        assert_eq!(p.to_canonical(), Positive::canonical(1));
+        let n = Negative::from_header_value(header::HeaderValue::U64(300));
+        assert_eq!(n.to_canonical(), Negative::canonical(300));
+        assert_eq!(
+            Byte(header::HeaderValue8::U8(0x10)).to_canonical(),
+            Byte::canonical(0x10)
+        );
+        assert_eq!(
+            Byte(header::HeaderValue8::U8(0x1a)).to_canonical().to_u8(),
+            0x1a
+        );
+        let tag = TagValue(header::HeaderValue::U16(24));
+        assert_eq!(tag.to_canonical(), TagValue::from_u64(24));
+        assert_eq!(
+            StructureLength::Definite(header::HeaderValue::U8(2)).to_canonical(),
+            StructureLength::from(2)
+        );
+        assert_eq!(
+            StructureLength::Indefinite.to_canonical(),
+            StructureLength::Indefinite
+        );
+    }
+
+    #[test]
+    fn read_discriminant() {
+        let data = [0x83, 0x02, 0x18, 0x2a, 0xf5];
+        let mut r = Reader::new(&data);
+        let (discriminant, array) = r.read_discriminant().unwrap();
+        assert_eq!(discriminant, 2);
+        assert_eq!(array.len(), 3);
+        assert_eq!(array[1].decode::<u64>().unwrap(), 42);
+        assert!(array[2].decode::<bool>().unwrap());
+        r.expect_finished().unwrap();
+
+        let mut r = Reader::new(&[0x80]);
+        assert!(matches!(
+            r.read_discriminant(),
+            Err(ReaderError::WrongExpectedLength {
+                expected: 1,
+                got: 0
+            })
+        ));
+
+        let mut r = Reader::new(&[0x81, 0x61, 0x61]);
+        assert!(matches!(
+            r.read_discriminant(),
+            Err(ReaderError::WrongExpectedType { .. })
+        ));
+    }
+
+    #[test]
+    fn reader_at_offset() {
+        let data = [0x83, 0x01, 0x62, 0x61, 0x62, 0x18, 0x2a];
+        let mut r = Reader::at_offset(&data, 5).unwrap();
+        assert_eq!(r.decode::<u64>().unwrap(), 42);
+        r.expect_finished().unwrap();
+
+        r.seek(2).unwrap();
+        assert_eq!(r.decode::<String>().unwrap(), "ab");
+        assert_eq!(r.consumed_bytes(), 5);
+
+        assert!(Reader::at_offset(&data, data.len()).unwrap().is_finished());
+        assert!(matches!(
+            Reader::at_offset(&data, 8),
+            Err(ReaderError::OffsetOutOfBounds { offset: 8, len: 7 })
+        ));
+        assert!(matches!(
+            r.seek(10),
+            Err(ReaderError::OffsetOutOfBounds { offset: 10, len: 7 })
+        ));
+        assert_eq!(r.consumed_bytes(), 5);
+    }
+
+    #[test]
+    fn tag_as_typed() {
+        let data = [0xc1, 0x1a, 0x00, 0x00, 0x00, 0x01];
+        let mut r = Reader::new(&data);
+        let tag = r.tag().unwrap();
+        let typed = tag.as_typed::<u64>().unwrap();
+        assert_eq!(typed.untype().reader().decode::<u64>().unwrap(), 1);
+        assert_eq!(typed.untype().as_ref(), &data[1..]);
+
+        assert!(tag.as_typed::<String>().is_err());
+    }
+
+    #[test]
+    fn tuple_roundtrip() {
+        let v = (1u64, "a".to_string());
+        assert_eq!(encode_to_bytes(&v), [0x82, 0x01, 0x61, 0x61]);
+        assert_eq!(
+            decode_from_bytes::<(u64, String)>(&[0x82, 0x01, 0x61, 0x61]).unwrap(),
+            v
+        );
+        assert_eq!(encode_to_bytes(&(true,)), [0x81, 0xf5]);
+        assert_eq!(encode_to_bytes(&()), [0x80]);
+        assert!(decode_from_bytes::<()>(&[0x80]).is_ok());
+        assert!(decode_from_bytes::<()>(&[0x9f, 0xff]).is_ok());
+        assert!(decode_from_bytes::<()>(&[0x81, 0x00]).is_err());
+
+        // values around the boundaries of the integer encodings
+        let vectors = [
+            0u64,
+            23,
+            24,
+            0xff,
+            0x100,
+            0xffff,
+            0x1_0000,
+            0xffff_ffff,
+            0x1_0000_0000,
+            0x9e37_79b9_7f4a_7c15,
+            u64::MAX,
+        ];
+        for n in vectors {
+            let v = (
+                n as u8,
+                (n >> 8) as u16,
+                (n >> 16) as u32,
+                n,
+                format!("{:x}", n),
+                n & 1 == 1,
+            );
+            let bytes = encode_to_bytes(&v);
+            assert_eq!(
+                decode_from_bytes::<(u8, u16, u32, u64, String, bool)>(&bytes).unwrap(),
+                v
+            );
+            let v = (n, (n as u32,));
+            let bytes = encode_to_bytes(&v);
+            assert_eq!(decode_from_bytes::<(u64, (u32,))>(&bytes).unwrap(), v);
+        }
+
+        // wrong number of elements
+        let err = decode_from_bytes::<(u64, u64)>(&[0x83, 0x01, 0x02, 0x03]).unwrap_err();
+        assert!(matches!(
+            err.error(),
+            DecodeErrorKind::UnexpectedLength {
+                expected: 2,
+                got: 3
+            }
+        ));
+    }
+
+    #[test]
+    fn decode_error_report() {
+        let err = decode_from_bytes::<(u64, u64)>(&[0x82, 0x01, 0x61, 0x61]).unwrap_err();
+        assert_eq!(err.offset(), None);
+        assert_eq!(
+            err.report(),
+            "failed to decode (u64, u64)->1->u64\n  error: ReaderError(\n      WrongExpectedType {\n          expected: Positive,\n          got: Text,\n      },\n  )\n"
+        );
+
+        let err = decode_from_bytes::<u64>(&[0x01, 0x02]).unwrap_err();
+        assert_eq!(err.offset(), Some(1));
+        assert!(err.report().ends_with("  at byte offset: 1\n"));
+    }
+
+    #[test]
+    fn writer_append_item_sequence() {
+        let items = [
+            encode_to_bytes(&1u64),
+            encode_to_bytes(&"ab".to_string()),
+            encode_to_bytes(&2u64),
+        ];
+        let mut writer = Writer::new();
+        for item in items.iter() {
+            writer.append_item(&decode_from_bytes::<CborData>(item).unwrap());
+        }
+        writer.encode(&3u64);
+        let bytes = writer.finalize();
+        assert_eq!(bytes, [0x01, 0x62, 0x61, 0x62, 0x02, 0x03]);
+
+        let mut r = Reader::new(&bytes);
+        let first: u64 = r.decode().unwrap();
+        assert_eq!(first, 1);
+        let second: String = r.decode().unwrap();
+        assert_eq!(second, "ab");
+        let rest = r
+            .decode_iter::<u64>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rest, vec![2, 3]);
+    }
+
+    #[test]
+    fn fixed_point() {
+        let v = Fixed::<2>(1050);
+        assert_eq!(encode_to_bytes(&v), [0x19, 0x04, 0x1a]);
+        assert_eq!(v.to_f64(), 10.5);
+
+        let v = Fixed::<2>::from_f64(-10.5).unwrap();
+        assert_eq!(v, Fixed(-1050));
+        assert_eq!(encode_to_bytes(&v), [0x39, 0x04, 0x19]);
+        assert_eq!(
+            decode_from_bytes::<Fixed<2>>(&[0x39, 0x04, 0x19]).unwrap(),
+            v
+        );
+        assert_eq!(v.to_f64(), -10.5);
+
+        assert_eq!(Fixed::<3>::from_f64(-0.0015), Some(Fixed(-2)));
+        assert_eq!(Fixed::<0>::from_f64(f64::NAN), None);
+        assert_eq!(Fixed::<10>::from_f64(1e10), None);
+
+        for v in [i64::MIN, -1, 0, 1, i64::MAX] {
+            let bytes = encode_to_bytes(&Fixed::<4>(v));
+            assert_eq!(decode_from_bytes::<Fixed<4>>(&bytes).unwrap(), Fixed(v));
+        }
+
+        // out of the i64 range
+        assert!(decode_from_bytes::<Fixed<2>>(&[0x1b, 0x80, 0, 0, 0, 0, 0, 0, 0]).is_err());
+        assert!(decode_from_bytes::<Fixed<2>>(&[0x3b, 0x80, 0, 0, 0, 0, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn decode_prefix() {
+        let bytes = [0x82, 0x01, 0x02, 0x18, 0x20, 0xff];
+        let (v, consumed) = decode_prefix_from_bytes::<(u64, u64)>(&bytes).unwrap();
+        assert_eq!(v, (1, 2));
+        assert_eq!(consumed, 3);
+        let (v, consumed) = decode_prefix_from_bytes::<u64>(&bytes[consumed..]).unwrap();
+        assert_eq!(v, 32);
+        assert_eq!(consumed, 2);
+        assert!(decode_prefix_from_bytes::<u64>(&[])
+            .unwrap_err()
+            .is_incomplete());
+    }
+
+    #[test]
+    fn sets() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let set: BTreeSet<u64> = [1000, 1, 24].into_iter().collect();
+        let bytes = encode_to_bytes(&set);
+        assert_eq!(bytes, [0x83, 0x01, 0x18, 0x18, 0x19, 0x03, 0xe8]);
+        assert_eq!(decode_from_bytes::<BTreeSet<u64>>(&bytes).unwrap(), set);
+
+        let hset: HashSet<u64> = set.iter().copied().collect();
+        assert_eq!(encode_to_bytes(&hset), bytes);
+        assert_eq!(decode_from_bytes::<HashSet<u64>>(&bytes).unwrap(), hset);
+
+        // sorted by the encoded elements, not by the `Ord` of the elements
+        let set: BTreeSet<String> = ["bb", "c"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(encode_to_bytes(&set), [0x82, 0x61, b'c', 0x62, b'b', b'b']);
+
+        // set tag 258 is accepted
+        let tagged = [0xd9, 0x01, 0x02, 0x82, 0x01, 0x02];
+        let set = decode_from_bytes::<BTreeSet<u64>>(&tagged).unwrap();
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![1, 2]);
+        assert!(decode_from_bytes::<BTreeSet<u64>>(&[0xc1, 0x82, 0x01, 0x02]).is_err());
+
+        // duplicated elements are rejected
+        assert!(decode_from_bytes::<BTreeSet<u64>>(&[0x82, 0x01, 0x01]).is_err());
+        assert!(decode_from_bytes::<HashSet<u64>>(&[0x82, 0x01, 0x01]).is_err());
+    }
+
+    #[test]
+    fn map_key_order() {
+        let data = [0xa3, 0x03, 0xf6, 0x01, 0xf6, 0x02, 0xf6];
+        let mut r = Reader::new(&data);
+        let map = r.map().unwrap();
+        assert_eq!(map.key_order::<u64>().unwrap(), vec![3, 1, 2]);
+        assert!(map.key_order::<String>().is_err());
+    }
+
+    #[test]
+    fn decode_validated() {
+        let non_empty = |s: &String| {
+            if s.is_empty() {
+                Err("empty string".to_string())
+            } else {
+                Ok(())
+            }
+        };
+        let mut r = Reader::new(&[0x61, 0x61, 0x60]);
+        assert_eq!(r.decode_validated(non_empty).unwrap(), "a");
+        let err = r.decode_validated(non_empty).unwrap_err();
+        assert!(matches!(err.error(), DecodeErrorKind::Custom(msg) if msg == "empty string"));
+        assert_eq!(err.context_as_path(), "alloc::string::String");
+        assert!(r.is_finished());
+    }
+
+    #[test]
+    fn array_tuple() {
+        let mut writer = Writer::new();
+        writer.encode(&(1u64, "a".to_string(), true));
+        let bytes = writer.finalize();
+
+        let mut reader = Reader::new(&bytes);
+        let (n, s, b) = reader.array_tuple::<(u64, String, bool)>().unwrap();
+        assert_eq!((n, s.as_str(), b), (1, "a", true));
+        reader.expect_finished().unwrap();
+
+        let err = Reader::new(&bytes)
+            .array_tuple::<(u64, bool, bool)>()
+            .unwrap_err();
+        assert_eq!(err.context()[1], "element 1");
+        assert_eq!(err.context_as_path(), "(u64, bool, bool)->element 1->bool");
+
+        let err = Reader::new(&bytes)
+            .array_tuple::<(u64, String)>()
+            .unwrap_err();
+        assert!(matches!(
+            err.error(),
+            DecodeErrorKind::UnexpectedLength {
+                expected: 2,
+                got: 3
+            }
+        ));
+    }
+
+    #[test]
+    fn fixed_bytes_array() {
+        let hashes = [[1u8; 32], [2u8; 32], [3u8; 32]];
+        let mut writer = Writer::new();
+        writer.array_build(StructureLength::from(hashes.len() as u64), |writer| {
+            for hash in hashes.iter() {
+                writer.encode(hash);
+            }
+        });
+        let bytes = writer.finalize();
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(reader.fixed_bytes_array::<32>().unwrap(), hashes);
+        reader.expect_finished().unwrap();
+
+        assert!(matches!(
+            Reader::new(&bytes).fixed_bytes_array::<28>(),
+            Err(ReaderError::WrongExpectedLength {
+                expected: 28,
+                got: 32
+            })
+        ));
+        assert!(matches!(
+            Reader::new(&[0x81, 0x00]).fixed_bytes_array::<1>(),
+            Err(ReaderError::WrongExpectedType { .. })
+        ));
+        // indefinite array and bytes
+        let indefinite = [
+            0x9f, 0x5f, 0x41, 0x01, 0x41, 0x02, 0xff, 0x42, 0x03, 0x04, 0xff,
+        ];
+        assert_eq!(
+            Reader::new(&indefinite).fixed_bytes_array::<2>().unwrap(),
+            [[1, 2], [3, 4]]
+        );
+    }
+
+    #[test]
+    fn map_canonical() {
+        // {1: 0, 24: 0, -1: 0, "a": 0}
+        const SORTED: &[u8] = &[
+            0xa4, 0x01, 0x00, 0x18, 0x18, 0x00, 0x20, 0x00, 0x61, 0x61, 0x00,
+        ];
+        assert_eq!(Reader::new(SORTED).map_canonical().unwrap().len(), 4);
+
+        // {1: 0, -1: 0, 24: 0}
+        const UNSORTED: &[u8] = &[0xa3, 0x01, 0x00, 0x20, 0x00, 0x18, 0x18, 0x00];
+        assert!(Reader::new(UNSORTED).map().is_ok());
+        assert!(matches!(
+            Reader::new(UNSORTED).map_canonical(),
+            Err(ReaderError::MapKeysNotSorted { at: 2 })
+        ));
+
+        // {1: 2, 1: 3}
+        const DUP: &[u8] = &[0xa2, 0x01, 0x02, 0x01, 0x03];
+        assert!(matches!(
+            Reader::new(DUP).map_canonical(),
+            Err(ReaderError::DuplicateKey {
+                first: 0,
+                duplicate: 1
+            })
+        ));
+
+        // {_ 1: 0}
+        assert!(matches!(
+            Reader::new(&[0xbf, 0x01, 0x00, 0xff]).map_canonical(),
+            Err(ReaderError::IndefiniteNotAllowed { got: Type::Map })
+        ));
+        // map length not in its shortest form
+        assert!(matches!(
+            Reader::new(&[0xb8, 0x01, 0x01, 0x00]).map_canonical(),
+            Err(ReaderError::NonCanonicalLength { got: Type::Map })
+        ));
+        // {1: 0} with the key 1 encoded on 2 bytes
+        let mut r = Reader::new(&[0xa1, 0x18, 0x01, 0x00]);
+        assert!(matches!(
+            r.map_canonical(),
+            Err(ReaderError::NonCanonicalInteger)
+        ));
+        assert_eq!(r.consumed_bytes(), 0);
+        // {(_ "a"): 0}
+        assert!(matches!(
+            Reader::new(&[0xa1, 0x7f, 0x61, 0x61, 0xff, 0x00]).map_canonical(),
+            Err(ReaderError::IndefiniteNotAllowed { got: Type::Text })
+        ));
+        // {1.0: 0} with the key as a double precision float
+        assert!(matches!(
+            Reader::new(&[0xa1, 0xfb, 0x3f, 0xf0, 0, 0, 0, 0, 0, 0, 0x00]).map_canonical(),
+            Err(ReaderError::NonCanonicalFloat)
+        ));
+        // {[1]: 0, 1.0: 0} with shortest keys
+        assert!(
+            Reader::new(&[0xa2, 0x81, 0x01, 0x00, 0xf9, 0x3c, 0x00, 0x00])
+                .map_canonical()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn peek_header_len() {
+        let vectors: &[(&[u8], usize)] = &[
+            (&[0x01], 1),
+            (&[0x18, 0x18], 2),
+            (&[0x39, 0x01, 0x00], 3),
+            (&[0x1a, 0x00, 0x01, 0x00, 0x00], 5),
+            (&[0x1b, 0, 0, 0, 1, 0, 0, 0, 0], 9),
+            (&[0x43, 0x01, 0x02, 0x03], 1),
+            (&[0x78, 0x01, 0x61], 2),
+            (&[0x9f, 0xff], 1),
+            (&[0xfb, 0x3f, 0xf0, 0, 0, 0, 0, 0, 0], 9),
+        ];
+        for (data, expected) in vectors {
+            let mut reader = Reader::new(data);
+            assert_eq!(reader.peek_header_len().unwrap(), *expected, "{:?}", data);
+            // not consumed
+            assert_eq!(reader.consumed_bytes(), 0);
+            reader.data().unwrap();
+        }
+        // only the lead byte is needed
+        assert_eq!(Reader::new(&[0x19]).peek_header_len().unwrap(), 3);
+        assert!(Reader::new(&[]).peek_header_len().is_err());
+    }
+
     pub struct Inner(u64, bool);
 
     impl Decode for Inner {
         fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
             let a = reader
-                .array()
+                .array_of_len(2)
                 .map_err(DecodeErrorKind::ReaderError)
                 .map_err(|e| e.context::<Self>())?;
-            if a.len() != 2 {
-                return Err(
-                    DecodeErrorKind::Custom(format!("expectig length of 2")).context::<Self>()
-                );
-            }
 
             let i = a[0].decode().expect("inner integer");
             let b = a[1].decode().expect("inner bool");
@@ -151,6 +2079,14 @@ mod tests {
         assert_eq!(e2.0, 1097911063);
         assert_eq!(e2.1, false);
         assert!(r.is_finished());
+
+        assert!(matches!(
+            Reader::new(&[0x81, 0x01]).array_of_len(2),
+            Err(ReaderError::WrongExpectedLength {
+                expected: 2,
+                got: 1
+            })
+        ));
     }
 
     #[test]
@@ -57,6 +57,11 @@ impl HeaderValue {
         }
     }
 
+    /// Get the shortest encoding of the same value
+    pub fn to_canonical(self) -> Self {
+        Self::canonical(self.to_u64())
+    }
+
     pub fn is_canonical(&self) -> bool {
         match self {
             // don't check if imm is < 24, as it shouldn't be allowed
@@ -116,6 +121,7 @@ pub enum Header {
     Constant(Constant),
     Float(Float),
     Byte(Byte),
+    Simple(u8),
     Break,
 }
 
@@ -138,6 +144,7 @@ impl Header {
             Header::Float(Float::FP32(_)) => Type::Float,
             Header::Float(Float::FP64(_)) => Type::Float,
             Header::Byte(_) => Type::Byte,
+            Header::Simple(_) => Type::Simple,
             Header::Break => Type::Break,
         }
     }
@@ -145,6 +152,9 @@ impl Header {
     pub fn from_parts(ld: Lead, ival: Option<IndirectValue>) -> Self {
         fn other_payload(val: Option<IndirectValue>) -> Header {
             match val {
+                // simple values from 32 are the extension simple values, anything below
+                // is kept as a (non canonical) byte to preserve the exact encoding
+                Some(IndirectValue::U8(v)) if v >= 0x20 => Header::Simple(v),
                 Some(IndirectValue::U8(v)) => Header::Byte(Byte(HeaderValue8::U8(v))),
                 Some(IndirectValue::U16(v)) => Header::Float(Float::FP16(v)),
                 Some(IndirectValue::U32(v)) => Header::Float(Float::FP32(v)),
@@ -189,6 +199,11 @@ impl HeaderValue8 {
         }
     }
 
+    /// Get the shortest encoding of the same value
+    pub fn to_canonical(self) -> Self {
+        Self::canonical(self.to_u8())
+    }
+
     pub fn is_canonical(&self) -> bool {
         match self {
             // don't check if imm is < 24, as it shouldn't be allowed
@@ -1,3 +1,4 @@
+/// CBOR major type, encoded in the high 3 bits of the lead byte
 #[derive(Debug, Clone, Copy)]
 pub enum Major {
     Positive,
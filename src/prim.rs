@@ -1,4 +1,4 @@
-use super::decode::{Decode, DecodeError, DecodeErrorKind};
+use super::decode::{Decode, DecodeBorrowed, DecodeError, DecodeErrorKind};
 use super::encode::Encode;
 use super::reader::Reader;
 use super::writer::Writer;
@@ -39,7 +39,23 @@ impl<'a> CborSlice {
         let t = <T>::decode(&mut reader)?;
         reader
             .expect_finished()
-            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(DecodeErrorKind::not_finished)
+            .map_err(|e| e.context::<T>())?;
+        Ok(t)
+    }
+
+    /// Decode a T, adding the `label` (e.g. a field name) to the error context on failure
+    pub fn decode_field<T: Decode>(&'a self, label: &'static str) -> Result<T, DecodeError> {
+        self.decode().map_err(|e| e.push_str(label))
+    }
+
+    /// Decode a T which may borrow from this slice
+    pub fn decode_borrowed<T: DecodeBorrowed<'a>>(&'a self) -> Result<T, DecodeError> {
+        let mut reader = self.reader();
+        let t = <T>::decode_borrowed(&mut reader)?;
+        reader
+            .expect_finished()
+            .map_err(DecodeErrorKind::not_finished)
             .map_err(|e| e.context::<T>())?;
         Ok(t)
     }
@@ -90,7 +106,7 @@ impl CborData {
         let t = <T>::decode(&mut reader)?;
         reader
             .expect_finished()
-            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(DecodeErrorKind::not_finished)
             .map_err(|e| e.context::<T>())?;
         Ok(t)
     }
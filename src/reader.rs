@@ -1,10 +1,14 @@
 use super::context::*;
 use super::decode::*;
+use super::event::Event;
 use super::header::*;
+use super::interner::Interner;
 use super::prim::*;
 use super::state::*;
 use super::types::*;
 use crate::lowlevel::lead::*;
+use std::borrow::Cow;
+use std::marker::PhantomData;
 
 /// Possible error when reading CBOR from a data stream
 #[derive(Debug, Clone)]
@@ -45,6 +49,35 @@ pub enum ReaderError {
     TextChunksInTextChunks,
     /// Indefinite bytes into another indefinite bytes
     BytesChunksInBytesChunks,
+    /// Integer not encoded in its smallest representation, rejected by the reader policy
+    NonCanonicalInteger,
+    /// Indefinite Bytes, Text, Array or Map, rejected by the reader policy
+    IndefiniteNotAllowed { got: Type },
+    /// Map with the same key (as encoded) at different indices, rejected by the reader policy
+    DuplicateKey { first: usize, duplicate: usize },
+    /// Map key at index `at` is not after the previous key in the canonical order,
+    /// as checked by `Reader::map_canonical`
+    MapKeysNotSorted { at: usize },
+    /// Structure nested deeper than allowed by the reader policy
+    MaxDepthExceeded { max: usize },
+    /// Allocation budget of the reader exhausted, see `Reader::with_alloc_budget`
+    LimitExceeded { limit: usize },
+    /// Content length is greater than the maximum allowed
+    TooLarge { max: usize, got: usize },
+    /// Text contains some non ASCII characters, where only ASCII was expected
+    TextNotAscii,
+    /// Text is rejected by the validation function of `Reader::text_validated`
+    TextValidationFailed,
+    /// Integer other than 0 or 1, where a boolean was expected by `Reader::lenient_bool`
+    IntegerNotBoolean { got: u64 },
+    /// Indefinite Text or Bytes made of chunks, where a contiguous slice was expected
+    ChunkedNotContiguous { got: Type },
+    /// Length of Bytes, Text, Array or Map not encoded in its smallest representation
+    NonCanonicalLength { got: Type },
+    /// Float not encoded in its smallest representation, as checked by `Reader::map_canonical`
+    NonCanonicalFloat,
+    /// Offset given to `Reader::at_offset` or `Reader::seek` is past the end of the data
+    OffsetOutOfBounds { offset: usize, len: usize },
     /// Unexpected type received in an indefinite Text where only definite Text chunk are allowed
     WrongExpectedTypeInText { got: Type },
     /// Unexpected type received in an indefinite Bytes where only definite Bytes chunk are allowed
@@ -75,9 +108,63 @@ impl From<CborDataMissing> for ReaderError {
     }
 }
 
+/// Maximum nesting depth of the elements handled by the recursive functions of this crate
+/// (`Reader::data_recursive`, `Decode for Value`, `DataOwned::to_json` and `diff`), when
+/// the policy of the reader doesn't set a lower one, which bounds their cost and stack usage
+pub const MAX_RECURSION_DEPTH: usize = 256;
+
+/// Strictness policy of a reader, applied to all the elements read through it
+///
+/// The policy is carried to the nested elements decoded by the `Decode` instances of this
+/// crate and by the derived ones, which use `Reader::nested_reader`. The readers created
+/// directly from a slice (`CborSlice::reader`, `Array::iter`, `Map::iter`, `Tag::read_data`)
+/// start with the default policy instead
+///
+/// The default policy accept everything that is valid CBOR
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReaderPolicy {
+    /// Reject integers not encoded in their smallest representation
+    pub require_canonical_ints: bool,
+    /// Accept indefinite bytes, text, array and map
+    pub allow_indefinite: bool,
+    /// Reject maps with the same key (as encoded) appearing multiple times
+    pub reject_duplicate_keys: bool,
+    /// Maximum number of arrays, maps and tags nested in each other
+    ///
+    /// Every structured read (`array`, `map`, `tag`, `data`, ...) checks the whole nested
+    /// content of the element against the current depth, whether the structures are then
+    /// read with `next_event` or decoded with nested readers
+    pub max_depth: usize,
+}
+
+impl Default for ReaderPolicy {
+    fn default() -> Self {
+        Self {
+            require_canonical_ints: false,
+            allow_indefinite: true,
+            reject_duplicate_keys: false,
+            max_depth: usize::MAX,
+        }
+    }
+}
+
 /// CBOR Data structure to read CBOR elements from a slice of byte
 pub struct Reader<'a> {
     reader: CborDataReader<'a>,
+    // structure validation state for the events returned by `next_event`
+    pub(crate) event_state: State,
+    // depth of this reader in the overall CBOR data, when tracked
+    depth: Option<usize>,
+    policy: ReaderPolicy,
+    alloc_budget: Option<AllocBudget>,
+    // interner for the decoded Rc<str> and Rc<[u8]>, see `Reader::with_interner`
+    interner: Option<&'a Interner>,
+}
+
+#[derive(Clone, Copy)]
+struct AllocBudget {
+    limit: usize,
+    remaining: usize,
 }
 
 macro_rules! matches_type {
@@ -92,6 +179,61 @@ macro_rules! matches_type {
     };
 }
 
+// check that the keys of a map are in their shortest encoding, and in the canonical order
+fn check_canonical_keys(map: &Map) -> Result<(), ReaderError> {
+    for (key, _) in map.elements.iter() {
+        check_canonical_element(key)?;
+    }
+    for (i, pair) in map.elements.windows(2).enumerate() {
+        match pair[0].0.as_ref().cmp(pair[1].0.as_ref()) {
+            std::cmp::Ordering::Less => {}
+            std::cmp::Ordering::Equal => {
+                return Err(ReaderError::DuplicateKey {
+                    first: i,
+                    duplicate: i + 1,
+                })
+            }
+            std::cmp::Ordering::Greater => return Err(ReaderError::MapKeysNotSorted { at: i + 1 }),
+        }
+    }
+    Ok(())
+}
+
+// check that every header of an element is definite and in its shortest form
+fn check_canonical_element(slice: &CborSlice) -> Result<(), ReaderError> {
+    let mut reader = slice.reader();
+    while !reader.is_finished() {
+        let (hdr, _) = reader.header()?;
+        match &hdr {
+            Header::Bytes(None) | Header::Text(None) | Header::Array(None) | Header::Map(None) => {
+                return Err(ReaderError::IndefiniteNotAllowed { got: hdr.to_type() })
+            }
+            Header::Bytes(Some(v))
+            | Header::Text(Some(v))
+            | Header::Array(Some(v))
+            | Header::Map(Some(v))
+                if !v.is_canonical() =>
+            {
+                return Err(ReaderError::NonCanonicalLength { got: hdr.to_type() })
+            }
+            Header::Positive(v) if !v.is_canonical() => {
+                return Err(ReaderError::NonCanonicalInteger)
+            }
+            Header::Negative(v) if !v.is_canonical() => {
+                return Err(ReaderError::NonCanonicalInteger)
+            }
+            Header::Tag(v) if !v.is_canonical() => return Err(ReaderError::NonCanonicalInteger),
+            Header::Byte(v) if !v.is_canonical() => return Err(ReaderError::NonCanonicalInteger),
+            Header::Float(f) if Float::canonical(f.to_f64()) != *f => {
+                return Err(ReaderError::NonCanonicalFloat)
+            }
+            _ => {}
+        }
+        reader.next_event()?;
+    }
+    Ok(())
+}
+
 fn state_process_header(state: &mut State, header: Header) -> Result<(), ReaderError> {
     match header {
         Header::Positive(_) => state.simple()?,
@@ -103,6 +245,7 @@ fn state_process_header(state: &mut State, header: Header) -> Result<(), ReaderE
         Header::Tag(_) => state.tag()?,
         Header::Constant(_) => state.simple()?,
         Header::Byte(_) => state.simple()?,
+        Header::Simple(_) => state.simple()?,
         Header::Float(_) => state.simple()?,
         Header::Break => state.brk()?,
     };
@@ -152,10 +295,160 @@ impl<'a> Reader<'a> {
         self.reader.peek(context, offset, n).map_err(|e| e.into())
     }
 
+    /// Create a new reader over `data`
+    ///
+    /// Empty data is accepted, and any attempt to read an element fails with `DataMissing`
     pub fn new(data: &'a [u8]) -> Self {
-        assert!(data.len() > 0);
         let reader = CborDataReader::new(data);
-        Self { reader }
+        Self {
+            reader,
+            event_state: State::new(),
+            depth: None,
+            policy: ReaderPolicy::default(),
+            alloc_budget: None,
+            interner: None,
+        }
+    }
+
+    /// Create a new reader on the data, starting at `offset` instead of the start of the data
+    ///
+    /// This allows to jump directly to an element at an already known offset, without reading
+    /// the preceding elements. An offset equal to the data length gives a finished reader
+    pub fn at_offset(data: &'a [u8], offset: usize) -> Result<Self, ReaderError> {
+        let mut reader = Self::new(data);
+        reader.seek(offset)?;
+        Ok(reader)
+    }
+
+    /// Move the reader to the absolute `offset` in its data
+    ///
+    /// The offset is not checked to be at an element boundary, and the state used by
+    /// `next_event` is reset, as the position is not related to the previous elements anymore
+    pub fn seek(&mut self, offset: usize) -> Result<(), ReaderError> {
+        let len = self.reader.index + self.reader.remaining_bytes();
+        if offset > len {
+            return Err(ReaderError::OffsetOutOfBounds { offset, len });
+        }
+        self.reader.index = offset;
+        self.event_state = State::new();
+        Ok(())
+    }
+
+    /// Create a new reader borrowing the content of a byte vector, which can be empty
+    #[allow(clippy::ptr_arg)]
+    pub fn from_vec(data: &'a Vec<u8>) -> Self {
+        Self::new(data.as_slice())
+    }
+
+    /// Create a new reader with a total allocation budget of `bytes`, returning a
+    /// `ReaderError::LimitExceeded` once exhausted
+    ///
+    /// Reading an array, map, bytes or text charges the budget for the element and all
+    /// the elements nested in it: the size of a slice reference for each element,
+    /// plus the content length of each bytes and text. Since the nested elements are already
+    /// accounted for, the readers created from their slices don't have a budget
+    pub fn with_alloc_budget(data: &'a [u8], bytes: usize) -> Self {
+        Self {
+            alloc_budget: Some(AllocBudget {
+                limit: bytes,
+                remaining: bytes,
+            }),
+            ..Self::new(data)
+        }
+    }
+
+    /// Create a new reader enforcing the strictness `policy`
+    ///
+    /// The policy is carried over to the readers of nested elements created with `nested_reader`,
+    /// `read_nested` and `decode_nested`, and the nesting depth is tracked to enforce the
+    /// maximum depth
+    pub fn with_policy(data: &'a [u8], policy: ReaderPolicy) -> Self {
+        Self {
+            depth: Some(0),
+            policy,
+            ..Self::new(data)
+        }
+    }
+
+    /// Create a new reader decoding `Rc<str>` and `Rc<[u8]>` through the `interner`,
+    /// so that repeated texts and bytes share the same allocation
+    ///
+    /// The interner is carried over to the readers of nested elements created with
+    /// `nested_reader`, and can be shared by multiple readers to deduplicate values
+    /// across documents
+    pub fn with_interner(data: &'a [u8], interner: &'a Interner) -> Self {
+        Self {
+            interner: Some(interner),
+            ..Self::new(data)
+        }
+    }
+
+    /// Return the interner of this reader, if any
+    pub fn interner(&self) -> Option<&'a Interner> {
+        self.interner
+    }
+
+    /// Return the strictness policy of this reader
+    pub fn policy(&self) -> ReaderPolicy {
+        self.policy
+    }
+
+    /// Create a new reader which keeps track of the nesting depth
+    ///
+    /// The depth is updated as the structures are opened and closed with `next_event`,
+    /// and carried over to the readers of nested elements created with `nested_reader`
+    pub fn with_depth_tracking(data: &'a [u8]) -> Self {
+        Self {
+            depth: Some(0),
+            ..Self::new(data)
+        }
+    }
+
+    /// Return the current nesting depth, counting the arrays, maps and tags enclosing
+    /// the next element
+    ///
+    /// For a reader without depth tracking, only the structures opened with `next_event`
+    /// are counted. A structured read (e.g. `array`) doesn't change the depth of this
+    /// reader, its elements are at the depth of the readers created with `nested_reader`
+    pub fn current_depth(&self) -> usize {
+        self.depth.unwrap_or(0) + self.event_state.depth()
+    }
+
+    /// Create a reader for an element nested in a structure read from this reader
+    /// (e.g. an element of an array), one level deeper than the current depth
+    ///
+    /// The nested reader tracks the depth only if this reader does
+    pub fn nested_reader(&self, slice: &'a CborSlice) -> Reader<'a> {
+        Reader {
+            depth: self.depth.map(|_| self.current_depth() + 1),
+            policy: self.policy,
+            interner: self.interner,
+            ..slice.reader()
+        }
+    }
+
+    /// Read an element nested in a structure read from this reader with a closure, using
+    /// a `nested_reader` and checking that the whole element is consumed once the closure returns
+    pub fn read_nested<F, T>(&self, slice: &'a CborSlice, f: F) -> Result<T, ReaderError>
+    where
+        F: FnOnce(&mut Reader<'a>) -> Result<T, ReaderError>,
+    {
+        let mut reader = self.nested_reader(slice);
+        let t = f(&mut reader)?;
+        reader.expect_finished()?;
+        Ok(t)
+    }
+
+    /// Decode a T from an element nested in a structure read from this reader, using
+    /// a `nested_reader` and checking that the whole element is consumed
+    pub fn decode_nested<T: Decode>(&self, slice: &'a CborSlice) -> Result<T, DecodeError> {
+        let mut reader = self.nested_reader(slice);
+        let t = <T>::decode(&mut reader)?;
+        reader
+            .expect_finished()
+            .map_err(DecodeErrorKind::not_finished)
+            .map_err(|e| e.context::<T>())?;
+        Ok(t)
     }
 
     /// read the byte header
@@ -206,9 +499,45 @@ impl<'a> Reader<'a> {
     fn header(&self) -> Result<(Header, usize), ReaderError> {
         let (ld, advance, ival) = self.header_parts()?;
         let header = Header::from_parts(ld, ival);
+        self.check_policy(&header)?;
         Ok((header, advance))
     }
 
+    // check the header against the integer and indefinite policy
+    fn check_policy(&self, header: &Header) -> Result<(), ReaderError> {
+        match header {
+            Header::Positive(v) if self.policy.require_canonical_ints && !v.is_canonical() => {
+                Err(ReaderError::NonCanonicalInteger)
+            }
+            Header::Negative(v) if self.policy.require_canonical_ints && !v.is_canonical() => {
+                Err(ReaderError::NonCanonicalInteger)
+            }
+            Header::Bytes(None) | Header::Text(None) | Header::Array(None) | Header::Map(None)
+                if !self.policy.allow_indefinite =>
+            {
+                Err(ReaderError::IndefiniteNotAllowed {
+                    got: header.to_type(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // check that a structure header opened with `enclosing` structures around it
+    // doesn't exceed the maximum depth of the policy
+    fn check_depth(&self, header: &Header, enclosing: usize) -> Result<(), ReaderError> {
+        match header {
+            Header::Array(_) | Header::Map(_) | Header::Tag(_)
+                if enclosing >= self.policy.max_depth =>
+            {
+                Err(ReaderError::MaxDepthExceeded {
+                    max: self.policy.max_depth,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
     fn advance_data(&mut self, header: &Header) -> Result<(), ReaderError> {
         match header {
             Header::Bytes(c) | Header::Text(c) => match c {
@@ -223,13 +552,86 @@ impl<'a> Reader<'a> {
         Ok(())
     }
 
+    // charge the allocation budget, if any, for the element of this header
+    fn charge(&mut self, header: &Header) -> Result<(), ReaderError> {
+        let Some(budget) = self.alloc_budget.as_mut() else {
+            return Ok(());
+        };
+        let cost = match header {
+            Header::Break => 0,
+            Header::Bytes(Some(b)) | Header::Text(Some(b)) => {
+                std::mem::size_of::<&CborSlice>().saturating_add(b.to_size())
+            }
+            _ => std::mem::size_of::<&CborSlice>(),
+        };
+        match budget.remaining.checked_sub(cost) {
+            None => Err(ReaderError::LimitExceeded {
+                limit: budget.limit,
+            }),
+            Some(remaining) => {
+                budget.remaining = remaining;
+                Ok(())
+            }
+        }
+    }
+
     /// Peek at the next type in the buffer
     ///
     /// Note that it can still return error if there's no data available (end of buffer),
     /// or that the CBOR lead byte is not well-formed
     pub fn peek_type(&self) -> Result<Type, ReaderError> {
         let lead = self.lead()?;
-        Ok(Type::from_lead(lead))
+        match lead {
+            // need the next byte to differentiate a Byte from a Simple value
+            Lead::ByteI1 => match self.header() {
+                Ok((hdr, _)) => Ok(hdr.to_type()),
+                // without the next byte, only the type of the lead byte is known
+                Err(_) => Ok(Type::from_lead(lead)),
+            },
+            _ => Ok(Type::from_lead(lead)),
+        }
+    }
+
+    /// Peek at the next type in the buffer, or None if there's no more data
+    ///
+    /// Contrary to `peek_type`, the end of buffer is not an error, which only
+    /// happens for a malformed or truncated lead
+    pub fn try_peek_type(&self) -> Result<Option<Type>, ReaderError> {
+        if self.is_finished() {
+            return Ok(None);
+        }
+        self.peek_type().map(Some)
+    }
+
+    /// Peek at the number of bytes taken by the header of the next element, without consuming it
+    ///
+    /// This is 1 for the lead byte, plus the 1, 2, 4 or 8 bytes of the integer or float
+    /// following it, if any. Only the lead byte need to be available, and the content of Bytes,
+    /// Text, Array and Map starts right after the header
+    pub fn peek_header_len(&self) -> Result<usize, ReaderError> {
+        let lead = self.lead()?;
+        Ok(match lead.expected_extra() {
+            None => 1,
+            Some(v) => 1 + v.len_bytes(),
+        })
+    }
+
+    /// Return whether the next element is a break, terminating an indefinite structure
+    pub fn at_break(&self) -> Result<bool, ReaderError> {
+        Ok(self.peek_type()? == Type::Break)
+    }
+
+    /// Consume a break, or return an error if the next element is not a break
+    pub fn consume_break(&mut self) -> Result<(), ReaderError> {
+        let got = self.peek_type()?;
+        if got != Type::Break {
+            return Err(ReaderError::WrongExpectedType {
+                expected: Type::Break,
+                got,
+            });
+        }
+        self.reader.advance(1);
+        Ok(())
     }
 
     pub fn positive(&mut self) -> Result<Positive, ReaderError> {
@@ -267,6 +669,14 @@ impl<'a> Reader<'a> {
         Ok(content)
     }
 
+    /// Read a simple value in the extension range (32 to 255)
+    pub fn simple(&mut self) -> Result<u8, ReaderError> {
+        let (hdr, advance) = self.header()?;
+        let content = matches_type!(hdr, Type::Simple, Header::Simple)?;
+        self.reader.advance(advance);
+        Ok(content)
+    }
+
     pub fn float(&mut self) -> Result<Float, ReaderError> {
         let (hdr, advance) = self.header()?;
         let content = matches_type!(hdr, Type::Float, Header::Float)?;
@@ -287,6 +697,22 @@ impl<'a> Reader<'a> {
         Ok(content)
     }
 
+    /// Read a constant and check that it is the expected constant `c`
+    pub fn expect_constant(&mut self, c: Constant) -> Result<(), ReaderError> {
+        let (hdr, advance) = self.header()?;
+        match hdr {
+            Header::Constant(constant) if constant == c => {}
+            _ => {
+                return Err(ReaderError::WrongExpectedType {
+                    expected: Header::Constant(c).to_type(),
+                    got: hdr.to_type(),
+                })
+            }
+        }
+        self.reader.advance(advance);
+        Ok(())
+    }
+
     pub fn null(&mut self) -> Result<(), ReaderError> {
         let (hdr, advance) = self.header()?;
         let _content = matches_type!(hdr, Type::Null, Header::Constant)?;
@@ -315,9 +741,33 @@ impl<'a> Reader<'a> {
         Ok(content)
     }
 
+    /// Read a boolean, either as the CBOR True/False constants or as the positive integers 1/0
+    ///
+    /// This is useful to interoperate with encoders using integers as booleans,
+    /// any other integer value is an error
+    pub fn lenient_bool(&mut self) -> Result<bool, ReaderError> {
+        let (hdr, advance) = self.header()?;
+        let content = match hdr {
+            Header::Constant(Constant::True) => Ok(true),
+            Header::Constant(Constant::False) => Ok(false),
+            Header::Positive(v) => match v.to_u64() {
+                0 => Ok(false),
+                1 => Ok(true),
+                got => Err(ReaderError::IntegerNotBoolean { got }),
+            },
+            _ => Err(ReaderError::WrongExpectedTypes {
+                expected: &[Type::False, Type::True, Type::Positive],
+                got: hdr.to_type(),
+            }),
+        }?;
+        self.reader.advance(advance);
+        Ok(content)
+    }
+
     pub fn bytes(&mut self) -> Result<Bytes<'a>, ReaderError> {
         let (hdr, advance) = self.header()?;
         let content = matches_type!(hdr, Type::Bytes, Header::Bytes)?;
+        self.charge(&hdr)?;
         self.reader.advance(advance);
         match content {
             // indefinite bytes
@@ -325,6 +775,7 @@ impl<'a> Reader<'a> {
                 let mut out = Vec::new();
                 loop {
                     let (hdr, advance) = self.header()?;
+                    self.charge(&hdr)?;
                     self.reader.advance(advance);
                     match hdr {
                         Header::Break => {
@@ -366,6 +817,7 @@ impl<'a> Reader<'a> {
     pub fn text(&mut self) -> Result<Text<'a>, ReaderError> {
         let (hdr, advance) = self.header()?;
         let content = matches_type!(hdr, Type::Text, Header::Text)?;
+        self.charge(&hdr)?;
 
         self.reader.advance(advance);
         match content {
@@ -374,6 +826,7 @@ impl<'a> Reader<'a> {
                 let mut out = Vec::new();
                 loop {
                     let (hdr, advance) = self.header()?;
+                    self.charge(&hdr)?;
                     self.reader.advance(advance);
                     match hdr {
                         Header::Break => {
@@ -405,10 +858,22 @@ impl<'a> Reader<'a> {
 
     /// return the slice of data of one next element (whatever it is)
     fn cbor_slice_neutral(&mut self) -> Result<&'a CborSlice, ReaderError> {
+        self.cbor_slice_at(self.current_depth())
+    }
+
+    // same as cbor_slice_neutral, for an element of a structure just read
+    fn cbor_slice_nested(&mut self) -> Result<&'a CborSlice, ReaderError> {
+        self.cbor_slice_at(self.current_depth() + 1)
+    }
+
+    // get the slice of the next element, which is enclosed in `enclosing` structures
+    fn cbor_slice_at(&mut self, enclosing: usize) -> Result<&'a CborSlice, ReaderError> {
         let start = self.reader.index;
         let mut state = State::new();
         loop {
             let (header, advance) = self.header()?;
+            self.check_depth(&header, enclosing + state.depth())?;
+            self.charge(&header)?;
             self.reader.advance(advance);
 
             self.advance_data(&header)?;
@@ -421,9 +886,173 @@ impl<'a> Reader<'a> {
         Ok(data)
     }
 
+    /// Read a Text containing only ASCII characters
+    pub fn ascii_text(&mut self) -> Result<String, ReaderError> {
+        let text = self.text()?.to_string();
+        if !text.is_ascii() {
+            return Err(ReaderError::TextNotAscii);
+        }
+        Ok(text)
+    }
+
+    /// Read a Text, and check that it is accepted by the validation function `f`
+    pub fn text_validated<F: Fn(&str) -> bool>(&mut self, f: F) -> Result<String, ReaderError> {
+        let text = self.text()?.to_string();
+        if !f(&text) {
+            return Err(ReaderError::TextValidationFailed);
+        }
+        Ok(text)
+    }
+
+    // read a Bytes of exactly `expected_len` bytes, leaving the element unconsumed
+    // when the length doesn't match
+    fn bytes_of_len(&mut self, expected_len: usize) -> Result<Bytes<'a>, ReaderError> {
+        let (hdr, _) = self.header()?;
+        if let Some(len) = matches_type!(hdr, Type::Bytes, Header::Bytes)? {
+            if len.to_size() != expected_len {
+                return Err(ReaderError::WrongExpectedLength {
+                    expected: expected_len,
+                    got: len.to_size(),
+                });
+            }
+            return self.bytes();
+        }
+        // the length of indefinite Bytes is only known once all the chunks are read
+        let start = self.reader.index;
+        let alloc_budget = self.alloc_budget;
+        let bytes = self.bytes()?;
+        if bytes.len() != expected_len {
+            self.reader.index = start;
+            self.alloc_budget = alloc_budget;
+            return Err(ReaderError::WrongExpectedLength {
+                expected: expected_len,
+                got: bytes.len(),
+            });
+        }
+        Ok(bytes)
+    }
+
+    /// Read a Bytes of exactly N bytes into an array
+    ///
+    /// A Bytes of a different length is reported as `WrongExpectedLength`,
+    /// without consuming the element
+    pub fn fixed_bytes<const N: usize>(&mut self) -> Result<[u8; N], ReaderError> {
+        let bytes = self.bytes_of_len(N)?;
+        let mut out = [0u8; N];
+        match bytes {
+            Bytes::Imm(bd) => out.copy_from_slice(bd.as_slice()),
+            Bytes::Chunks(_) => out.copy_from_slice(&bytes.to_vec()),
+        }
+        Ok(out)
+    }
+
+    /// Read an Array where each element is a Bytes of exactly N bytes, e.g. a list of hashes
+    ///
+    /// The output is allocated once for the whole array, and each element is copied in place.
+    /// Note that the elements cannot be borrowed as a `&[[u8; N]]`, as each Bytes is preceded
+    /// by its header in the data, so the contents are never contiguous.
+    ///
+    /// An element that is not a Bytes of N bytes is reported as `WrongExpectedType`
+    /// or `WrongExpectedLength`
+    pub fn fixed_bytes_array<const N: usize>(&mut self) -> Result<Vec<[u8; N]>, ReaderError> {
+        let array = self.array()?;
+        let mut out = Vec::with_capacity(array.len());
+        for mut element in array.iter() {
+            out.push(element.fixed_bytes::<N>()?);
+        }
+        Ok(out)
+    }
+
+    /// Read a definite Bytes, borrowing the content from the reader data
+    ///
+    /// Indefinite Bytes are rejected, as their chunks are not contiguous
+    pub fn bytes_slice(&mut self) -> Result<&'a [u8], ReaderError> {
+        let (hdr, _) = self.header()?;
+        if let Header::Bytes(None) = hdr {
+            return Err(ReaderError::ChunkedNotContiguous { got: Type::Bytes });
+        }
+        match self.bytes()? {
+            Bytes::Imm(bd) => Ok(bd.as_slice()),
+            Bytes::Chunks(_) => unreachable!(),
+        }
+    }
+
+    /// Read a Bytes of exactly `width` bytes, and assemble them as a big endian unsigned integer
+    ///
+    /// A width greater than 8 returns `ReaderError::TooLarge` without reading anything,
+    /// and Bytes of another length return `ReaderError::WrongExpectedLength` without consuming them
+    pub fn be_uint_from_bytes(&mut self, width: usize) -> Result<u64, ReaderError> {
+        if width > 8 {
+            return Err(ReaderError::TooLarge { max: 8, got: width });
+        }
+        let bytes = self.bytes_of_len(width)?;
+        let mut buf = [0u8; 8];
+        match &bytes {
+            Bytes::Imm(bd) => buf[8 - width..].copy_from_slice(bd.as_slice()),
+            Bytes::Chunks(_) => buf[8 - width..].copy_from_slice(&bytes.to_vec()),
+        }
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    /// Read a definite Bytes of exactly `expected_len` bytes with a canonical length header,
+    /// borrowing the content from the reader data
+    ///
+    /// This is meant for strict parsing of cryptographic material, where any other encoding is
+    /// rejected with `ChunkedNotContiguous`, `NonCanonicalLength` or `WrongExpectedLength`,
+    /// without consuming the element
+    pub fn canonical_bytes_slice(&mut self, expected_len: usize) -> Result<&'a [u8], ReaderError> {
+        let (hdr, _) = self.header()?;
+        let len = match matches_type!(hdr, Type::Bytes, Header::Bytes)? {
+            None => return Err(ReaderError::ChunkedNotContiguous { got: Type::Bytes }),
+            Some(len) => len,
+        };
+        if !len.is_canonical() {
+            return Err(ReaderError::NonCanonicalLength { got: Type::Bytes });
+        }
+        if len.to_size() != expected_len {
+            return Err(ReaderError::WrongExpectedLength {
+                expected: expected_len,
+                got: len.to_size(),
+            });
+        }
+        self.bytes_slice()
+    }
+
+    /// Read a definite Text, borrowing the content from the reader data
+    ///
+    /// Indefinite Text are rejected, as their chunks are not contiguous
+    pub fn text_str(&mut self) -> Result<&'a str, ReaderError> {
+        let (hdr, _) = self.header()?;
+        if let Header::Text(None) = hdr {
+            return Err(ReaderError::ChunkedNotContiguous { got: Type::Text });
+        }
+        match self.text()? {
+            Text::Imm(td) => Ok(td.as_str()),
+            Text::Chunks(_) => unreachable!(),
+        }
+    }
+
+    /// Read a Text, borrowing the content from the reader data when definite,
+    /// and only allocating to concatenate the chunks of an indefinite Text
+    pub fn text_cow(&mut self) -> Result<Cow<'a, str>, ReaderError> {
+        match self.text()? {
+            Text::Imm(td) => Ok(Cow::Borrowed(td.as_str())),
+            text @ Text::Chunks(_) => Ok(Cow::Owned(text.to_string())),
+        }
+    }
+
+    /// Return the validated slice of data of the next element, whatever its type
+    ///
+    /// The element is consumed from the reader, but not interpreted
+    pub fn raw_slice(&mut self) -> Result<&'a CborSlice, ReaderError> {
+        self.cbor_slice_neutral()
+    }
+
     pub fn array(&mut self) -> Result<Array<'a>, ReaderError> {
         let (hdr, advance) = self.header()?;
+        self.check_depth(&hdr, self.current_depth())?;
         let content = matches_type!(hdr, Type::Array, Header::Array)?;
+        self.charge(&hdr)?;
 
         self.reader.advance(advance);
 
@@ -432,12 +1061,12 @@ impl<'a> Reader<'a> {
             // indefinite Array
             None => {
                 // loop for cbor slices until we find a cbor break
-                while self.peek_type()? != Type::Break {
-                    let data = self.cbor_slice_neutral()?;
+                while !self.at_break()? {
+                    let data = self.cbor_slice_nested()?;
                     elements.push(data);
                 }
                 // skip the break now that we found it
-                self.reader.advance(1);
+                self.consume_break()?;
 
                 Ok(Array {
                     len_encoding: content.into(),
@@ -448,7 +1077,7 @@ impl<'a> Reader<'a> {
             Some(len) => {
                 let sz = len.to_size();
                 for _ in 0..sz {
-                    let data = self.cbor_slice_neutral()?;
+                    let data = self.cbor_slice_nested()?;
                     elements.push(data);
                 }
 
@@ -460,54 +1089,171 @@ impl<'a> Reader<'a> {
         }
     }
 
+    /// Read an Array, and check that it contains exactly `n` elements,
+    /// or otherwise return a `ReaderError::WrongExpectedLength`
+    pub fn array_of_len(&mut self, n: usize) -> Result<Array<'a>, ReaderError> {
+        let array = self.array()?;
+        if array.len() != n {
+            return Err(ReaderError::WrongExpectedLength {
+                expected: n,
+                got: array.len(),
+            });
+        }
+        Ok(array)
+    }
+
+    /// Read a definite Array of exactly `n` elements
+    ///
+    /// An indefinite Array is rejected with `ReaderError::IndefiniteNotAllowed`, whatever the
+    /// reader policy, and a definite Array of another length with `ReaderError::WrongExpectedLength`.
+    /// In both cases, the array is not consumed
+    pub fn definite_array_of_len(&mut self, n: usize) -> Result<Array<'a>, ReaderError> {
+        let (hdr, _) = self.header()?;
+        match matches_type!(hdr, Type::Array, Header::Array)? {
+            None => return Err(ReaderError::IndefiniteNotAllowed { got: Type::Array }),
+            Some(len) if len.to_size() != n => {
+                return Err(ReaderError::WrongExpectedLength {
+                    expected: n,
+                    got: len.to_size(),
+                })
+            }
+            Some(_) => {}
+        }
+        self.array()
+    }
+
+    /// Read an Array where the first element is an integer discriminant, as used by
+    /// the tagvariant enum encoding, and return the discriminant along with the Array
+    ///
+    /// The returned Array still contains the discriminant as its first element, so the
+    /// variant fields start at index 1. An empty Array is rejected with
+    /// `ReaderError::WrongExpectedLength`
+    pub fn read_discriminant(&mut self) -> Result<(u64, Array<'a>), ReaderError> {
+        let array = self.array()?;
+        let discriminant = match array.get(0) {
+            None => {
+                return Err(ReaderError::WrongExpectedLength {
+                    expected: 1,
+                    got: 0,
+                })
+            }
+            Some(mut r) => r.positive()?.to_u64(),
+        };
+        Ok((discriminant, array))
+    }
+
     pub fn map(&mut self) -> Result<Map<'a>, ReaderError> {
         let (hdr, advance) = self.header()?;
+        self.check_depth(&hdr, self.current_depth())?;
         let content = matches_type!(hdr, Type::Map, Header::Map)?;
+        self.charge(&hdr)?;
 
         self.reader.advance(advance);
 
         let mut elements = Vec::new();
-        match content {
+        let map = match content {
             // indefinite Map
             None => {
                 // loop for cbor key/value slices until we find a cbor break
-                while self.peek_type()? != Type::Break {
-                    let key = self.cbor_slice_neutral()?;
-                    let value = self.cbor_slice_neutral()?;
+                while !self.at_break()? {
+                    let key = self.cbor_slice_nested()?;
+                    let value = self.cbor_slice_nested()?;
                     elements.push((key, value));
                 }
 
                 // skip the break now that we found it
-                self.reader.advance(1);
+                self.consume_break()?;
 
-                Ok(Map {
+                Map {
                     len_encoding: content.into(),
                     elements,
-                })
+                }
             }
             // definite Map
             Some(len) => {
                 let sz = len.to_size();
                 for _ in 0..sz {
-                    let key = self.cbor_slice_neutral()?;
-                    let value = self.cbor_slice_neutral()?;
+                    let key = self.cbor_slice_nested()?;
+                    let value = self.cbor_slice_nested()?;
                     elements.push((key, value));
                 }
 
-                Ok(Map {
+                Map {
                     len_encoding: content.into(),
                     elements,
-                })
+                }
+            }
+        };
+        if self.policy.reject_duplicate_keys {
+            let mut keys = std::collections::HashMap::with_capacity(map.elements.len());
+            for (i, (k, _)) in map.elements.iter().enumerate() {
+                if let Some(first) = keys.insert(k.as_ref(), i) {
+                    return Err(ReaderError::DuplicateKey {
+                        first,
+                        duplicate: i,
+                    });
+                }
             }
         }
+        Ok(map)
+    }
+
+    /// Read a Map following the deterministic encoding of RFC 8949 for the map and its keys:
+    /// a definite map with a shortest length, keys in the canonical order (bytewise lexicographic
+    /// order of their encoding) without duplicates, and keys in their shortest encoding
+    ///
+    /// An indefinite map, or an indefinite element in a key, is reported as
+    /// `ReaderError::IndefiniteNotAllowed`, a length not in its shortest form as
+    /// `ReaderError::NonCanonicalLength`, an integer or tag not in its shortest form as
+    /// `ReaderError::NonCanonicalInteger` and a float not in its shortest form as
+    /// `ReaderError::NonCanonicalFloat`. A key equal to the previous one is reported as
+    /// `ReaderError::DuplicateKey`, and a key ordered before the previous one as
+    /// `ReaderError::MapKeysNotSorted`. The map is not consumed on error.
+    ///
+    /// The values are only checked according to the policy of the reader
+    pub fn map_canonical(&mut self) -> Result<Map<'a>, ReaderError> {
+        let (hdr, _) = self.header()?;
+        match matches_type!(hdr, Type::Map, Header::Map)? {
+            None => return Err(ReaderError::IndefiniteNotAllowed { got: Type::Map }),
+            Some(len) if !len.is_canonical() => {
+                return Err(ReaderError::NonCanonicalLength { got: Type::Map })
+            }
+            Some(_) => {}
+        }
+        let start = self.reader.index;
+        let alloc_budget = self.alloc_budget;
+        let map = self.map()?;
+        if let Err(e) = check_canonical_keys(&map) {
+            self.reader.index = start;
+            self.alloc_budget = alloc_budget;
+            return Err(e);
+        }
+        Ok(map)
+    }
+
+    /// Skip the self-described CBOR magic tag (Tag 55799) if present, returning whether it was
+    ///
+    /// The reader is then positioned at the tagged content
+    pub fn skip_magic(&mut self) -> Result<bool, ReaderError> {
+        if self.is_finished() {
+            return Ok(false);
+        }
+        match self.header()? {
+            (Header::Tag(v), advance) if v.to_u64() == 55799 => {
+                self.reader.advance(advance);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
     }
 
     pub fn tag(&mut self) -> Result<Tag<'a>, ReaderError> {
         let (hdr, advance) = self.header()?;
+        self.check_depth(&hdr, self.current_depth())?;
         let tag_val = TagValue(matches_type!(hdr, Type::Tag, Header::Tag)?);
 
         self.reader.advance(advance);
-        let data = self.cbor_slice_neutral()?;
+        let data = self.cbor_slice_nested()?;
 
         Ok(Tag { tag_val, data })
     }
@@ -528,14 +1274,254 @@ impl<'a> Reader<'a> {
             Type::Undefined => self.constant().map(|_| Data::Undefined),
             Type::Float => self.float().map(Data::Float),
             Type::Byte => self.byte().map(Data::Byte),
+            Type::Simple => self.simple().map(Data::Simple),
             Type::Break => Err(ReaderError::UnexpectedBreakType),
         }
     }
 
+    /// Read the next element as a fully decoded tree, where the contents of arrays,
+    /// maps and tags are recursively decoded instead of kept as CBOR slices
+    ///
+    /// The depth limit and policy of the reader apply to the nested elements, and the element
+    /// cannot nest more than `MAX_RECURSION_DEPTH` arrays, maps and tags
+    pub fn data_recursive(&mut self) -> Result<DataTree<'a>, ReaderError> {
+        let policy = self.policy;
+        let depth = self.depth;
+        let max_depth = self.current_depth().saturating_add(MAX_RECURSION_DEPTH);
+        self.policy.max_depth = policy.max_depth.min(max_depth);
+        self.depth = Some(depth.unwrap_or(0));
+        let tree = self.data_tree();
+        self.policy = policy;
+        self.depth = depth;
+        tree
+    }
+
+    // the recursion of data_recursive, with a depth tracked and limited by the policy
+    fn data_tree(&mut self) -> Result<DataTree<'a>, ReaderError> {
+        let ty = self.peek_type()?;
+        match ty {
+            Type::Positive => self.positive().map(DataTree::Positive),
+            Type::Negative => self.negative().map(DataTree::Negative),
+            Type::Bytes => self.bytes().map(DataTree::Bytes),
+            Type::Text => self.text().map(DataTree::Text),
+            Type::Array => {
+                let array = self.array()?;
+                let elements = array
+                    .elements
+                    .iter()
+                    .map(|slice| self.nested_reader(slice).data_tree())
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(DataTree::Array(array.len_encoding, elements))
+            }
+            Type::Map => {
+                let map = self.map()?;
+                let elements = map
+                    .elements
+                    .iter()
+                    .map(|(k, v)| {
+                        let key = self.nested_reader(k).data_tree()?;
+                        let value = self.nested_reader(v).data_tree()?;
+                        Ok((key, value))
+                    })
+                    .collect::<Result<Vec<_>, ReaderError>>()?;
+                Ok(DataTree::Map(map.len_encoding, elements))
+            }
+            Type::Tag => {
+                let tag = self.tag()?;
+                let value = self.nested_reader(tag.data).data_tree()?;
+                Ok(DataTree::Tag(tag.tag_val, Box::new(value)))
+            }
+            Type::False => self.constant().map(|_| DataTree::False),
+            Type::True => self.constant().map(|_| DataTree::True),
+            Type::Null => self.constant().map(|_| DataTree::Null),
+            Type::Undefined => self.constant().map(|_| DataTree::Undefined),
+            Type::Float => self.float().map(DataTree::Float),
+            Type::Byte => self.byte().map(DataTree::Byte),
+            Type::Simple => self.simple().map(DataTree::Simple),
+            Type::Break => Err(ReaderError::UnexpectedBreakType),
+        }
+    }
+
+    /// Read all the remaining elements of the reader, as a CBOR sequence of owned data
+    pub fn collect_remaining(&mut self) -> Result<Vec<DataOwned>, ReaderError> {
+        let mut out = Vec::new();
+        while !self.is_finished() {
+            let data = self.data()?;
+            out.push(data.owned());
+        }
+        Ok(out)
+    }
+
+    /// Walk all the remaining elements of the reader, returning the offset, the type
+    /// and the encoded length in bytes of each of them
+    ///
+    /// Offsets are relative to the start of the reader. The walk stops at the end of the data
+    /// or at the first element that cannot be read, leaving the reader before this element
+    pub fn debug_walk(&mut self) -> Vec<(usize, Type, usize)> {
+        let mut out = Vec::new();
+        while !self.is_finished() {
+            let offset = self.consumed_bytes();
+            match self.peek_type().and_then(|ty| Ok((ty, self.raw_slice()?))) {
+                Ok((ty, slice)) => out.push((offset, ty, slice.as_ref().len())),
+                Err(_) => {
+                    // the element might be partially consumed
+                    self.reader.index = offset;
+                    break;
+                }
+            }
+        }
+        out
+    }
+
+    /// Read the next CBOR event, for processing CBOR without building whole elements
+    ///
+    /// The structure of the events is validated across calls, for example a `Break` is only
+    /// accepted when an indefinite structure is opened. Mixing `next_event` with the other
+    /// reading methods is only supported when all the structures opened by events are terminated.
+    ///
+    /// Any error is considered fatal for the event processing.
+    pub fn next_event(&mut self) -> Result<Event<'a>, ReaderError> {
+        let (hdr, advance) = self.header()?;
+        self.check_depth(&hdr, self.current_depth())?;
+        let event = match hdr {
+            Header::Positive(v) => Event::Positive(v),
+            Header::Negative(v) => Event::Negative(v),
+            Header::Bytes(None) => Event::BytesStart,
+            Header::Bytes(Some(b)) => {
+                let data = self.peek_at(CborDataContext::Content, advance, b.to_size())?;
+                Event::Bytes(BytesData(b, data))
+            }
+            Header::Text(None) => Event::TextStart,
+            Header::Text(Some(b)) => {
+                let data = self.peek_at(CborDataContext::Content, advance, b.to_size())?;
+                let data_str = std::str::from_utf8(data).map_err(ReaderError::TextUTF8Error)?;
+                Event::Text(TextData(b, data_str))
+            }
+            Header::Array(c) => Event::ArrayStart(c.into()),
+            Header::Map(c) => Event::MapStart(c.into()),
+            Header::Tag(v) => Event::Tag(TagValue(v)),
+            Header::Constant(Constant::True) => Event::True,
+            Header::Constant(Constant::False) => Event::False,
+            Header::Constant(Constant::Null) => Event::Null,
+            Header::Constant(Constant::Undefined) => Event::Undefined,
+            Header::Float(v) => Event::Float(v),
+            Header::Byte(v) => Event::Byte(v),
+            Header::Simple(v) => Event::Simple(v),
+            Header::Break => Event::Break,
+        };
+        state_process_header(&mut self.event_state, hdr.clone())?;
+        self.reader.advance(advance);
+        self.advance_data(&hdr)?;
+        Ok(event)
+    }
+
     pub fn decode<T: Decode>(&mut self) -> Result<T, DecodeError> {
         <T>::decode(self)
     }
 
+    /// Decode a T and check it with the validation function `f`
+    ///
+    /// When the validation fails, the message is returned as a `DecodeErrorKind::Custom` error
+    /// in the context of T. Note that the element is consumed whether the validation passes or not
+    pub fn decode_validated<T, F>(&mut self, f: F) -> Result<T, DecodeError>
+    where
+        T: Decode,
+        F: Fn(&T) -> Result<(), String>,
+    {
+        let t = <T>::decode(self)?;
+        f(&t).map_err(|msg| DecodeErrorKind::Custom(msg).context::<T>())?;
+        Ok(t)
+    }
+
+    /// Decode an array of known shape directly into a tuple, e.g. `(u64, String, bool)`
+    ///
+    /// The array length has to match the number of elements of the tuple, otherwise a
+    /// `DecodeErrorKind::UnexpectedLength` is returned. An element failing to decode
+    /// has its position added to the error context (e.g. `element 1`)
+    pub fn array_tuple<T: ArrayTuple>(&mut self) -> Result<T, DecodeError> {
+        let array = self
+            .array()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<T>())?;
+        if array.len() != T::LEN {
+            return Err(DecodeErrorKind::UnexpectedLength {
+                expected: T::LEN,
+                got: array.len(),
+            }
+            .context::<T>());
+        }
+        T::decode_elements(self, &array)
+    }
+
+    /// Decode a T from a byte string containing its complete CBOR encoding
+    ///
+    /// The content of the byte string is read with the policy, interner and allocation budget
+    /// of this reader, one level deeper, and any trailing data inside the byte string is an error
+    pub fn nested_cbor<T: Decode>(&mut self) -> Result<T, DecodeError> {
+        let bytes = self
+            .bytes()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<T>())?;
+        let data = match &bytes {
+            Bytes::Imm(data) => Cow::Borrowed(data.as_slice()),
+            Bytes::Chunks(_) => Cow::Owned(bytes.to_vec()),
+        };
+        // the content was only charged as bytes, so its elements are charged to the same budget
+        let mut reader = Reader {
+            depth: self.depth.map(|_| self.current_depth() + 1),
+            policy: self.policy,
+            alloc_budget: self.alloc_budget,
+            interner: self.interner,
+            ..Reader::new(&data)
+        };
+        let t = reader.decode();
+        self.alloc_budget = reader.alloc_budget;
+        let t = t?;
+        reader
+            .expect_finished()
+            .map_err(DecodeErrorKind::not_finished)
+            .map_err(|e| e.context::<T>())?;
+        Ok(t)
+    }
+
+    /// Get an iterator decoding T elements one after the other, until the reader is finished
+    ///
+    /// This is useful to process a CBOR sequence. The iterator stops after returning the first error
+    pub fn decode_iter<T: Decode>(&mut self) -> DecodeIter<'_, 'a, T> {
+        DecodeIter {
+            reader: self,
+            failed: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Decode a T which may borrow from the reader data
+    pub fn decode_borrowed<T: DecodeBorrowed<'a>>(&mut self) -> Result<T, DecodeError> {
+        <T>::decode_borrowed(self)
+    }
+
+    /// Try each decoder in turn, returning the result of the first one that succeeds.
+    ///
+    /// The reader is rewound to its original position after each failed attempt, refunding
+    /// the allocation budget used by the attempt, and if all the decoders fail, the error
+    /// contains the error of each attempt
+    pub fn decode_any_of<T>(&mut self, decoders: &[DecodeFn<T>]) -> Result<T, DecodeError> {
+        let start = self.reader.index;
+        let alloc_budget = self.alloc_budget;
+        let mut errors = Vec::with_capacity(decoders.len());
+        for decoder in decoders {
+            match decoder(self) {
+                Ok(t) => return Ok(t),
+                Err(e) => {
+                    self.reader.index = start;
+                    self.alloc_budget = alloc_budget;
+                    errors.push(e)
+                }
+            }
+        }
+        Err(DecodeErrorKind::NoMatchingAlternative(errors).context::<T>())
+    }
+
     pub fn decode_one<T: Decode>(&mut self) -> Result<T, DecodeError> {
         let t = <T>::decode(self)?;
         let remaining_bytes = self.remaining_bytes();
@@ -554,6 +1540,15 @@ impl<'a> Reader<'a> {
         slice.validate_as()
     }
 
+    /// Decode a T, and also return the exact bytes it was decoded from
+    ///
+    /// The element is only decoded once, the slice covering the bytes consumed by the decoding
+    pub fn decode_with_raw<T: Decode>(&mut self) -> Result<(T, &'a CborSlice), DecodeError> {
+        let start = self.reader.index;
+        let t = <T>::decode(self)?;
+        Ok((t, self.reader.slice_from(start)))
+    }
+
     pub fn exact_decodable_data<T: Decode>(&mut self) -> Result<CborDataOf<T>, DecodeError> {
         let slice = self
             .cbor_slice_neutral()
@@ -562,3 +1557,23 @@ impl<'a> Reader<'a> {
         slice.validate_as().map(|slice| slice.to_owned())
     }
 }
+
+/// Iterator decoding elements from a reader until it is finished, created by `Reader::decode_iter`
+pub struct DecodeIter<'r, 'a, T> {
+    reader: &'r mut Reader<'a>,
+    failed: bool,
+    phantom: PhantomData<T>,
+}
+
+impl<'r, 'a, T: Decode> Iterator for DecodeIter<'r, 'a, T> {
+    type Item = Result<T, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.reader.is_finished() {
+            return None;
+        }
+        let item = self.reader.decode();
+        self.failed = item.is_err();
+        Some(item)
+    }
+}
@@ -0,0 +1,75 @@
+//! Resumable reader for incremental parsing of CBOR data received in pieces
+
+use super::event::Event;
+use super::reader::{Reader, ReaderError};
+use super::state::State;
+
+/// Reader of CBOR events over a growable buffer, which can be fed with more data
+/// as it becomes available (e.g. from a network stream).
+///
+/// The structure state is kept across calls, so an element can be split at any byte
+/// between two `feed`s. When not enough data is buffered to return the next event,
+/// `next_event` returns `Ok(None)` instead of a `DataMissing` error, and the same
+/// event is attempted again on the next call.
+pub struct ResumableReader {
+    buffer: Vec<u8>,
+    position: usize,
+    state: State,
+}
+
+impl Default for ResumableReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResumableReader {
+    /// Create a new resumable reader with an empty buffer
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            position: 0,
+            state: State::new(),
+        }
+    }
+
+    /// Append more data to the buffer
+    ///
+    /// The data already consumed by previous events is discarded at this point
+    pub fn feed(&mut self, data: &[u8]) {
+        self.buffer.drain(..self.position);
+        self.position = 0;
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Return the number of bytes buffered and not yet consumed
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffer.len() - self.position
+    }
+
+    /// Return whether all the buffered data has been consumed, and no structure is
+    /// still opened, which means the events so far form complete CBOR elements
+    pub fn is_finished(&self) -> bool {
+        self.buffered_bytes() == 0 && self.state.acceptable()
+    }
+
+    /// Try to read the next event, returning `Ok(None)` if more data need to be fed
+    pub fn next_event(&mut self) -> Result<Option<Event<'_>>, ReaderError> {
+        if self.buffered_bytes() == 0 {
+            return Ok(None);
+        }
+        let mut reader = Reader::new(&self.buffer[self.position..]);
+        reader.event_state = std::mem::replace(&mut self.state, State::new());
+        let result = reader.next_event();
+        // the state is only updated once all the data of the event is available
+        self.state = std::mem::replace(&mut reader.event_state, State::new());
+        match result {
+            Ok(event) => {
+                self.position += reader.consumed_bytes();
+                Ok(Some(event))
+            }
+            Err(ReaderError::DataMissing(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
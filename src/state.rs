@@ -17,7 +17,7 @@
 pub use super::header::{HeaderValue, HeaderValueStream};
 use std::fmt;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum StreamType {
     Array,
     Map(bool),
@@ -97,6 +97,8 @@ impl fmt::Display for StateError {
 
 pub struct State {
     ctx: Vec<StructTy>,
+    // number of arrays, maps and tags in ctx
+    depth: usize,
 }
 
 #[derive(Clone, Copy)]
@@ -108,7 +110,35 @@ pub enum ContextAction {
 impl State {
     /// Create a new empty validation state
     pub fn new() -> Self {
-        Self { ctx: vec![] }
+        Self {
+            ctx: vec![],
+            depth: 0,
+        }
+    }
+
+    /// return the number of arrays, maps and tags currently opened
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    // indefinite bytes and texts are not counted in the depth
+    fn counted(ty: &StructTy) -> bool {
+        !matches!(ty, StructTy::Stream(s) if s.composite_scalar())
+    }
+
+    fn push(&mut self, ty: StructTy) {
+        if Self::counted(&ty) {
+            self.depth += 1;
+        }
+        self.ctx.push(ty)
+    }
+
+    fn pop(&mut self) -> Option<StructTy> {
+        let ty = self.ctx.pop();
+        if matches!(&ty, Some(ty) if Self::counted(ty)) {
+            self.depth -= 1;
+        }
+        ty
     }
 
     /// return if the state is in a stable accepted position
@@ -125,7 +155,7 @@ impl State {
             _ => (),
         };
 
-        self.ctx.push(StructTy::Stream(ty));
+        self.push(StructTy::Stream(ty));
         Ok(())
     }
 
@@ -161,7 +191,7 @@ impl State {
     }
 
     fn reduce(&mut self) -> Result<(), StateError> {
-        match self.ctx.pop() {
+        match self.pop() {
             Some(StructTy::Array(elements)) => {
                 if elements > 0 {
                     Err(StateError::StructureNotFinished)
@@ -220,6 +250,13 @@ impl State {
             }
             // A regular text or bytes, we can just call item_simple
             Some(_b) => {
+                // in a bytes/texts stream, only chunks of the same type are allowed
+                match self.ctx.last() {
+                    Some(StructTy::Stream(s)) if s.composite_scalar() && *s != new_stream => {
+                        return Err(StateError::InvalidTypeInChunk);
+                    }
+                    _ => (),
+                };
                 self.advance()?;
                 self.check_reduce()
             }
@@ -250,7 +287,7 @@ impl State {
             }
             Some(number_of_items) => {
                 let sz = number_of_items.to_size();
-                self.ctx.push(f(sz));
+                self.push(f(sz));
                 self.check_reduce()?;
             }
         };
@@ -261,6 +298,12 @@ impl State {
     ///
     /// All values except text, bytes, array, map, tag
     pub fn simple(&mut self) -> Result<(), StateError> {
+        match self.ctx.last() {
+            Some(StructTy::Stream(s)) if s.composite_scalar() => {
+                return Err(StateError::InvalidTypeInChunk);
+            }
+            _ => (),
+        };
         self.advance()?;
         self.check_reduce()
     }
@@ -296,7 +339,7 @@ impl State {
 
     /// Process a CBOR break, which terminate either an indefinite array, map, bytes, text
     pub fn brk(&mut self) -> Result<(), StateError> {
-        match self.ctx.pop() {
+        match self.pop() {
             Some(StructTy::Stream(_sty)) => (),
             Some(_sty) => {
                 return Err(StateError::BreakInNonStreamable);
@@ -319,7 +362,7 @@ impl State {
             _ => (),
         };
 
-        self.ctx.push(StructTy::Tag(false));
+        self.push(StructTy::Tag(false));
         Ok(())
     }
 }
@@ -422,6 +465,23 @@ mod tests {
         assert!(state.acceptable());
     }
 
+    #[test]
+    fn chunk_invalid() {
+        let mut state = State::new();
+        assert_state_ok!(state.text(None));
+        assert!(matches!(
+            state.simple(),
+            Err(StateError::InvalidTypeInChunk)
+        ));
+
+        let mut state = State::new();
+        assert_state_ok!(state.bytes(None));
+        assert!(matches!(
+            state.text(Some(HeaderValue::U64(1))),
+            Err(StateError::InvalidTypeInChunk)
+        ));
+    }
+
     #[test]
     fn tag() {
         let mut state = State::new();
@@ -14,6 +14,37 @@ pub struct PositiveBignum(TagValue, BytesOwned);
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct NegativeBignum(TagValue, BytesOwned);
 
+/// CBOR Decimal Fraction (Tag 4)
+///
+/// Represent the number `mantissa * 10^exponent`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecimalFraction {
+    tag: TagValue,
+    len_encoding: StructureLength,
+    exponent: Scalar,
+    mantissa: DecimalMantissa,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecimalMantissa {
+    Positive(Positive),
+    Negative(Negative),
+    PositiveBignum(PositiveBignum),
+    NegativeBignum(NegativeBignum),
+}
+
+/// CBOR item expected to be converted to base64url if converted to text (Tag 21)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpectedBase64Url(TagValue, CborData);
+
+/// CBOR item expected to be converted to base64 if converted to text (Tag 22)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpectedBase64(TagValue, CborData);
+
+/// CBOR item expected to be converted to base16 if converted to text (Tag 23)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpectedBase16(TagValue, CborData);
+
 /// CBOR data in CBOR (Tag 24)
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EncodedCBOR(TagValue, BytesOwned);
@@ -41,6 +72,16 @@ pub enum RationalDenominator {
     PositiveBignum(PositiveBignum),
 }
 
+/// Any tag with its content decoded as a T
+///
+/// The tag value is written back canonically, so non-canonical tag encodings
+/// are not preserved by a decode / encode cycle
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tagged<T> {
+    pub tag: u64,
+    pub value: T,
+}
+
 macro_rules! matches_tag {
     ($reader:ident, $value:literal) => {{
         let tag = $reader.tag()?;
@@ -122,6 +163,226 @@ impl NegativeBignum {
 
 encode_decode!(NegativeBignum);
 
+impl DecimalFraction {
+    pub fn read<'a>(reader: &mut Reader<'a>) -> Result<Self, ReaderError> {
+        let tag = matches_tag!(reader, 4);
+        let decimal = reader.read_nested(tag.data(), |reader| {
+            let array = reader.array()?;
+            if array.len() != 2 {
+                return Err(ReaderError::WrongExpectedLength {
+                    expected: 2,
+                    got: array.len(),
+                });
+            }
+            let exponent = {
+                let mut inner_reader = reader.nested_reader(array[0]);
+                let res = inner_reader.scalar()?;
+                inner_reader.expect_finished()?;
+                res
+            };
+            let mantissa = {
+                let mut inner_reader = reader.nested_reader(array[1]);
+                let res = match inner_reader.peek_type()? {
+                    Type::Positive => inner_reader.positive().map(DecimalMantissa::Positive),
+                    Type::Negative => inner_reader.negative().map(DecimalMantissa::Negative),
+                    Type::Tag => {
+                        // the tag header is consumed, so dispatch on the tag value instead
+                        // of trying each bignum reader in turn
+                        let tag = inner_reader.tag()?;
+                        let bytes = tag.read_data(|reader| reader.bytes())?.owned();
+                        match tag.value() {
+                            2 => Ok(DecimalMantissa::PositiveBignum(PositiveBignum(
+                                tag.tag_repr(),
+                                bytes,
+                            ))),
+                            3 => Ok(DecimalMantissa::NegativeBignum(NegativeBignum(
+                                tag.tag_repr(),
+                                bytes,
+                            ))),
+                            got => Err(ReaderError::WrongExpectedTags {
+                                expected: &[2, 3],
+                                got,
+                            }),
+                        }
+                    }
+                    ty => Err(ReaderError::WrongExpectedTypes {
+                        expected: &[Type::Positive, Type::Negative, Type::Tag],
+                        got: ty,
+                    }),
+                }?;
+                inner_reader.expect_finished()?;
+                res
+            };
+            Ok(DecimalFraction {
+                tag: tag.tag_repr(),
+                len_encoding: array.len_encoding,
+                exponent,
+                mantissa,
+            })
+        })?;
+        Ok(decimal)
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        // check that it's encoding a value of 2 if defined
+        match self.len_encoding {
+            StructureLength::Indefinite => {}
+            StructureLength::Definite(v) if v.to_u64() == 2 => {}
+            StructureLength::Definite(v) => {
+                panic!("DecimalFraction length encoding is not 2, {}", v.to_u64())
+            }
+        };
+        writer.tag_build(self.tag, |writer| {
+            writer.array_build(self.len_encoding, |writer| {
+                writer.scalar(self.exponent);
+                match &self.mantissa {
+                    DecimalMantissa::Positive(v) => writer.positive(*v),
+                    DecimalMantissa::Negative(v) => writer.negative(*v),
+                    DecimalMantissa::PositiveBignum(v) => v.write(writer),
+                    DecimalMantissa::NegativeBignum(v) => v.write(writer),
+                }
+            })
+        })
+    }
+
+    /// Create a canonically encoded decimal fraction representing `mantissa * 10^exponent`
+    ///
+    /// The mantissa is encoded as a bignum only when it doesn't fit in a CBOR integer
+    pub fn from_parts(mantissa: i128, exponent: i32) -> Self {
+        let exponent = if exponent >= 0 {
+            Scalar::canonical_positive(exponent as u64)
+        } else {
+            Scalar::canonical_negative((-1 - exponent as i64) as u64)
+        };
+        let mantissa = if mantissa >= 0 {
+            match u64::try_from(mantissa) {
+                Ok(v) => DecimalMantissa::Positive(Positive::canonical(v)),
+                Err(_) => DecimalMantissa::PositiveBignum(PositiveBignum(
+                    TagValue::from_u64(2),
+                    BytesOwned::from_vec(minimal_be_bytes(mantissa as u128)),
+                )),
+            }
+        } else {
+            let n = (-1 - mantissa) as u128;
+            match u64::try_from(n) {
+                Ok(v) => DecimalMantissa::Negative(Negative::canonical(v)),
+                Err(_) => DecimalMantissa::NegativeBignum(NegativeBignum(
+                    TagValue::from_u64(3),
+                    BytesOwned::from_vec(minimal_be_bytes(n)),
+                )),
+            }
+        };
+        DecimalFraction {
+            tag: TagValue::from_u64(4),
+            len_encoding: StructureLength::from(2),
+            exponent,
+            mantissa,
+        }
+    }
+
+    pub fn exponent(&self) -> Scalar {
+        self.exponent
+    }
+
+    pub fn mantissa(&self) -> &DecimalMantissa {
+        &self.mantissa
+    }
+
+    /// Compute the approximate value `mantissa * 10^exponent` as a f64
+    ///
+    /// The result is subject to the usual floating point precision loss, notably
+    /// for mantissa larger than 2^53 or when the exponent is not small.
+    /// A value too big to be represented returns positive or negative infinity
+    /// according to the sign of the mantissa, and a value too small returns zero.
+    pub fn to_f64(&self) -> f64 {
+        let mantissa = match &self.mantissa {
+            DecimalMantissa::Positive(v) => v.to_u64() as f64,
+            DecimalMantissa::Negative(v) => -1.0 - v.negative_u64() as f64,
+            DecimalMantissa::PositiveBignum(v) => be_bytes_to_f64(&v.to_be_bytes()),
+            DecimalMantissa::NegativeBignum(v) => -1.0 - be_bytes_to_f64(&v.to_be_bytes()),
+        };
+        if mantissa == 0.0 {
+            return 0.0;
+        }
+        let exponent = match self.exponent {
+            Scalar::Positive(v) => i32::try_from(v.to_u64()).unwrap_or(i32::MAX),
+            Scalar::Negative(v) => i32::try_from(v.negative_u64())
+                .map(|v| -1 - v)
+                .unwrap_or(i32::MIN),
+        };
+        // dividing for negative exponents keep the result exact for small values
+        // (e.g. 0.1 instead of 0.1000000000000000055)
+        if exponent >= 0 {
+            mantissa * 10f64.powi(exponent)
+        } else if exponent >= -22 {
+            // powers of 10 up to 10^22 are exact, so the division is correctly rounded
+            mantissa / 10f64.powi(exponent.saturating_neg())
+        } else {
+            // larger powers of 10 are rounded or not representable (above 10^308), but the
+            // parsing of the decimal notation is correctly rounded down to the subnormal values.
+            // An infinite mantissa (bignum out of the f64 range) is kept as is
+            format!("{}e{}", mantissa, exponent)
+                .parse()
+                .unwrap_or(mantissa)
+        }
+    }
+}
+
+fn minimal_be_bytes(v: u128) -> Vec<u8> {
+    let bytes = v.to_be_bytes();
+    let first = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    bytes[first..].to_vec()
+}
+
+fn be_bytes_to_f64(bytes: &[u8]) -> f64 {
+    bytes.iter().fold(0.0, |acc, b| acc * 256.0 + *b as f64)
+}
+
+encode_decode!(DecimalFraction);
+
+// the expected conversion tags wrap any CBOR item, which is kept as is
+macro_rules! expected_conversion {
+    ($type:ident, $value:literal) => {
+        impl $type {
+            pub fn read<'a>(reader: &mut Reader<'a>) -> Result<Self, ReaderError> {
+                let tag = matches_tag!(reader, $value);
+                let data = tag.read_data(|reader| reader.raw_slice())?;
+                Ok($type(tag.tag_repr(), data.to_owned()))
+            }
+
+            fn write(&self, writer: &mut Writer) {
+                writer.tag_build(self.0, |writer| writer.encode(&self.1));
+            }
+
+            /// Get the tagged CBOR item
+            pub fn data(&self) -> &CborData {
+                &self.1
+            }
+
+            /// Wrap some CBOR item with the conversion hint
+            pub fn from_data(data: CborData) -> Self {
+                $type(TagValue::from_u64($value), data)
+            }
+
+            /// Decode the tagged CBOR item as a T
+            pub fn decode_inner<T: Decode>(&self) -> Result<T, DecodeError> {
+                self.1.decode().map_err(|e| e.push::<Self>())
+            }
+
+            /// Encode a T, and wrap the resulting CBOR item with the conversion hint
+            pub fn from_encodable<T: Encode>(value: &T) -> Self {
+                Self::from_data(CborData(encode_to_bytes(value)))
+            }
+        }
+
+        encode_decode!($type);
+    };
+}
+
+expected_conversion!(ExpectedBase64Url, 21);
+expected_conversion!(ExpectedBase64, 22);
+expected_conversion!(ExpectedBase16, 23);
+
 impl EncodedCBOR {
     pub fn read<'a>(reader: &mut Reader<'a>) -> Result<Self, ReaderError> {
         let tag = matches_tag!(reader, 24);
@@ -144,6 +405,16 @@ impl EncodedCBOR {
             BytesOwned::from_vec(cbor_bytes.to_vec()),
         )
     }
+
+    /// Decode the embedded CBOR data as a T
+    pub fn decode_inner<T: Decode>(&self) -> Result<T, DecodeError> {
+        decode_from_bytes(&self.to_bytes()).map_err(|e| e.push::<Self>())
+    }
+
+    /// Encode a T, and embed the resulting CBOR data
+    pub fn from_encodable<T: Encode>(value: &T) -> Self {
+        Self::from_bytes(&encode_to_bytes(value))
+    }
 }
 
 encode_decode!(EncodedCBOR);
@@ -151,7 +422,7 @@ encode_decode!(EncodedCBOR);
 impl RationalNumber {
     pub fn read<'a>(reader: &mut Reader<'a>) -> Result<Self, ReaderError> {
         let tag = matches_tag!(reader, 30);
-        let rational = tag.read_data(|reader| {
+        let rational = reader.read_nested(tag.data(), |reader| {
             let array = reader.array()?;
             if array.len() != 2 {
                 return Err(ReaderError::WrongExpectedLength {
@@ -160,7 +431,7 @@ impl RationalNumber {
                 });
             }
             let numerator = {
-                let mut inner_reader = array[0].reader();
+                let mut inner_reader = reader.nested_reader(array[0]);
                 let res = match inner_reader.peek_type()? {
                     Type::Positive => inner_reader.positive().map(RationalNumerator::Positive),
                     Type::Negative => inner_reader.negative().map(RationalNumerator::Negative),
@@ -179,7 +450,7 @@ impl RationalNumber {
                 res
             };
             let denominator = {
-                let mut inner_reader = array[1].reader();
+                let mut inner_reader = reader.nested_reader(array[1]);
                 let res = match inner_reader.peek_type()? {
                     Type::Positive => inner_reader.positive().map(RationalDenominator::Positive),
                     Type::Tag => PositiveBignum::read(&mut inner_reader)
@@ -237,3 +508,33 @@ impl RationalNumber {
 }
 
 encode_decode!(RationalNumber);
+
+impl<T> Tagged<T> {
+    pub fn new(tag: u64, value: T) -> Self {
+        Self { tag, value }
+    }
+}
+
+impl<T: Decode> Decode for Tagged<T> {
+    fn decode<'a>(reader: &mut Reader<'a>) -> Result<Self, DecodeError> {
+        let tag = reader
+            .tag()
+            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(|e| e.context::<Self>())?;
+        let value = reader
+            .decode_nested(tag.data())
+            .map_err(|e| e.push::<Self>())?;
+        Ok(Tagged {
+            tag: tag.value(),
+            value,
+        })
+    }
+}
+
+impl<T: Encode> Encode for Tagged<T> {
+    fn encode(&self, writer: &mut Writer) {
+        writer.tag_build(TagValue::from_u64(self.tag), |writer| {
+            writer.encode(&self.value)
+        });
+    }
+}
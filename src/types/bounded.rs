@@ -0,0 +1,60 @@
+/// Bytes of at most N bytes, stored inline without allocation
+///
+/// Encoded and decoded as a CBOR Bytes, which is rejected when longer than N
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoundedBytes<const N: usize> {
+    // the bytes after `len` are always 0
+    pub(crate) data: [u8; N],
+    pub(crate) len: usize,
+}
+
+impl<const N: usize> BoundedBytes<N> {
+    /// Create empty bounded bytes
+    pub fn new() -> Self {
+        Self {
+            data: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Create bounded bytes from a slice, or None if the slice is longer than N
+    pub fn from_slice(slice: &[u8]) -> Option<Self> {
+        let mut bytes = Self::new();
+        bytes.extend_from_slice(slice).then_some(bytes)
+    }
+
+    // append the slice if the total length stays within N, return whether it was appended
+    pub(crate) fn extend_from_slice(&mut self, slice: &[u8]) -> bool {
+        let end = self.len + slice.len();
+        if end > N {
+            return false;
+        }
+        self.data[self.len..end].copy_from_slice(slice);
+        self.len = end;
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+}
+
+impl<const N: usize> Default for BoundedBytes<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for BoundedBytes<N> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
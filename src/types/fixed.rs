@@ -0,0 +1,34 @@
+/// Fixed-point number, made of an integer scaled by `10^SCALE`
+///
+/// The value represented is `self.0 / 10^SCALE`, so `Fixed::<2>(-1050)` represents `-10.50`.
+/// Encoded and decoded as a CBOR Positive or Negative integer of the scaled value,
+/// which keeps the value exact contrary to a float
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed<const SCALE: u32>(pub i64);
+
+impl<const SCALE: u32> Fixed<SCALE> {
+    fn factor() -> f64 {
+        10f64.powi(SCALE as i32)
+    }
+
+    /// Get the scaled integer value
+    pub fn scaled(self) -> i64 {
+        self.0
+    }
+
+    /// Convert to the closest f64 of the value represented
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::factor()
+    }
+
+    /// Create from a f64, rounding to the nearest value representable with the scale
+    ///
+    /// Return None if the value is not finite, or is out of range once scaled
+    pub fn from_f64(v: f64) -> Option<Self> {
+        let scaled = (v * Self::factor()).round();
+        if !scaled.is_finite() || scaled < i64::MIN as f64 || scaled >= i64::MAX as f64 {
+            return None;
+        }
+        Some(Self(scaled as i64))
+    }
+}
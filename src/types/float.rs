@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+
 /// CBOR Float (FP16, FP32, FP64)
 #[derive(Clone, Debug, Copy, PartialEq, Eq)]
 pub enum Float {
@@ -24,6 +26,79 @@ impl Float {
             Float::FP64(fp) => f64::from_bits(*fp),
         }
     }
+
+    /// Create a double precision float with the exact bits of `f`
+    ///
+    /// The sign of zero and the NaN payloads are kept as is
+    pub fn from_f64_exact(f: f64) -> Float {
+        Float::FP64(f.to_bits())
+    }
+
+    /// Create a half precision float from `f`, rounding to the nearest representable
+    /// value (ties to even) and overflowing to infinity
+    ///
+    /// This bypass canonicalization: the half precision form is always used, which
+    /// allows to reproduce a specific encoding
+    pub fn fp16_of(f: f32) -> Float {
+        Float::FP16(ieee754_u32_to_u16(f.to_bits()))
+    }
+
+    /// Create a normal precision float with the exact bits of `f`
+    ///
+    /// This bypass canonicalization: the normal precision form is always used, even
+    /// when a half precision float would represent the same value
+    pub fn fp32_of(f: f32) -> Float {
+        Float::FP32(f.to_bits())
+    }
+
+    /// Create a double precision float with the exact bits of `f`
+    ///
+    /// This bypass canonicalization: the double precision form is always used, even
+    /// when a shorter float would represent the same value
+    pub fn fp64_of(f: f64) -> Float {
+        Float::FP64(f.to_bits())
+    }
+
+    /// Create the shortest float representing exactly `f`, trying half precision,
+    /// then normal precision and finally double precision
+    ///
+    /// Every NaN is represented by the half precision quiet NaN, following the
+    /// deterministic encoding of RFC 8949
+    pub fn canonical(f: f64) -> Float {
+        if f.is_nan() {
+            return Float::FP16(0x7e00);
+        }
+        let single = f as f32;
+        if (single as f64).to_bits() != f.to_bits() {
+            return Float::FP64(f.to_bits());
+        }
+        match ieee754_u32_to_u16_exact(single.to_bits()) {
+            Some(half) => Float::FP16(half),
+            None => Float::FP32(single.to_bits()),
+        }
+    }
+
+    /// Compare two floats using the IEEE754 total order on their double precision value
+    ///
+    /// Unlike `PartialOrd` on native floats, this is defined for NaN,
+    /// and order -0.0 strictly before +0.0
+    pub fn total_cmp(&self, other: &Float) -> Ordering {
+        self.to_f64().total_cmp(&other.to_f64())
+    }
+
+    /// Get the bit pattern of the double precision value of this float, where
+    /// every NaN is mapped to the same quiet NaN.
+    ///
+    /// Two floats of different precision representing the same value have the same
+    /// canonical bits, which make it suitable as a sorting or hashing key
+    pub fn canonical_bits(&self) -> u64 {
+        let v = self.to_f64();
+        if v.is_nan() {
+            f64::NAN.to_bits()
+        } else {
+            v.to_bits()
+        }
+    }
 }
 
 // convert a u16 holding a IEEE754 FP16 to a u32 representing a IEEE754 FP32
@@ -71,3 +146,190 @@ fn ieee754_u16_to_u32(v: u16) -> u32 {
         sign | exp | (fp16frac << 13)
     }
 }
+
+// convert a u32 holding a non NaN IEEE754 FP32 to a u16 representing a IEEE754 FP16,
+// only if the conversion doesn't lose any precision
+fn ieee754_u32_to_u16_exact(v: u32) -> Option<u16> {
+    let sign = ((v >> 16) & 0x8000) as u16;
+    let exp = ((v >> 23) & 0xff) as i32;
+    let frac = v & 0x007F_FFFF;
+
+    // zero and infinity
+    if v & 0x7FFF_FFFF == 0 {
+        return Some(sign);
+    }
+    if exp == 0xff {
+        return Some(sign | 0x7c00);
+    }
+    // FP32 subnormals are too small for FP16
+    if exp == 0 {
+        return None;
+    }
+
+    let unbiased_exp = exp - 127;
+    if (-14..=15).contains(&unbiased_exp) {
+        // normal, dropping 13 bits of fraction
+        if frac & 0x1fff != 0 {
+            return None;
+        }
+        Some(sign | (((unbiased_exp + 15) as u16) << 10) | (frac >> 13) as u16)
+    } else if (-24..-14).contains(&unbiased_exp) {
+        // subnormal, representing mantissa * 2^-24
+        let mantissa = 0x0080_0000 | frac;
+        let shift = -(unbiased_exp + 1) as u32;
+        if mantissa & ((1 << shift) - 1) != 0 {
+            return None;
+        }
+        Some(sign | (mantissa >> shift) as u16)
+    } else {
+        None
+    }
+}
+
+// convert a u32 holding a IEEE754 FP32 to a u16 representing a IEEE754 FP16,
+// rounding to nearest (ties to even) when the value is not representable
+fn ieee754_u32_to_u16(v: u32) -> u16 {
+    let sign = ((v >> 16) & 0x8000) as u16;
+    let exp = ((v >> 23) & 0xff) as i32;
+    let frac = v & 0x007F_FFFF;
+
+    // Infinity and NaN, keeping the NaN quiet
+    if exp == 0xff {
+        if frac == 0 {
+            return sign | 0x7c00;
+        } else {
+            return sign | 0x7e00 | (frac >> 13) as u16;
+        }
+    }
+    // zero and FP32 subnormals are too small for FP16
+    if exp == 0 {
+        return sign;
+    }
+
+    let unbiased_exp = exp - 127;
+    if unbiased_exp > 15 {
+        sign | 0x7c00
+    } else if unbiased_exp >= -14 {
+        // normal, dropping 13 bits of fraction. a carry from the rounding
+        // goes into the exponent, up to infinity
+        let half = (((unbiased_exp + 15) as u32) << 10) | (frac >> 13);
+        let rem = frac & 0x1fff;
+        let rounded = if rem > 0x1000 || (rem == 0x1000 && half & 1 == 1) {
+            half + 1
+        } else {
+            half
+        };
+        sign | rounded as u16
+    } else {
+        // subnormal, representing mantissa * 2^-24
+        let mantissa = 0x0080_0000 | frac;
+        let shift = -(unbiased_exp + 1) as u32;
+        if shift > 24 {
+            return sign;
+        }
+        let half = mantissa >> shift;
+        let rem = mantissa & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        let rounded = if rem > halfway || (rem == halfway && half & 1 == 1) {
+            half + 1
+        } else {
+            half
+        };
+        sign | rounded as u16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn total_cmp() {
+        let neg_zero = Float::FP16(0x8000);
+        let pos_zero = Float::FP32(0);
+        let one = Float::FP64(1.0f64.to_bits());
+        let nan = Float::FP16(0x7e00);
+        assert_eq!(neg_zero.total_cmp(&pos_zero), Ordering::Less);
+        assert_eq!(pos_zero.total_cmp(&one), Ordering::Less);
+        assert_eq!(one.total_cmp(&nan), Ordering::Less);
+        assert_eq!(Float::FP16(0x3c00).total_cmp(&one), Ordering::Equal);
+    }
+
+    #[test]
+    fn canonical() {
+        // RFC 8949 Appendix A
+        let vectors: &[(f64, Float)] = &[
+            (0.0, Float::FP16(0x0000)),
+            (-0.0, Float::FP16(0x8000)),
+            (1.0, Float::FP16(0x3c00)),
+            (1.1, Float::FP64(0x3ff1_9999_9999_999a)),
+            (1.5, Float::FP16(0x3e00)),
+            (65504.0, Float::FP16(0x7bff)),
+            (100000.0, Float::FP32(0x47c3_5000)),
+            (3.4028234663852886e+38, Float::FP32(0x7f7f_ffff)),
+            (1.0e+300, Float::FP64(0x7e37_e43c_8800_759c)),
+            (5.960464477539063e-8, Float::FP16(0x0001)),
+            (0.00006103515625, Float::FP16(0x0400)),
+            (-4.0, Float::FP16(0xc400)),
+            (-4.1, Float::FP64(0xc010_6666_6666_6666)),
+            (f64::INFINITY, Float::FP16(0x7c00)),
+            (f64::NAN, Float::FP16(0x7e00)),
+            (f64::NEG_INFINITY, Float::FP16(0xfc00)),
+        ];
+        for (f, expected) in vectors {
+            let canonical = Float::canonical(*f);
+            assert_eq!(canonical, *expected, "{}", f);
+            if !f.is_nan() {
+                assert_eq!(canonical.to_f64().to_bits(), f.to_bits());
+            }
+        }
+        // not representable as a FP16 subnormal
+        assert_eq!(
+            Float::canonical(3.0 * 2f64.powi(-25)),
+            Float::FP32((3.0 * 2f32.powi(-25)).to_bits())
+        );
+    }
+
+    #[test]
+    fn explicit_width() {
+        assert_eq!(Float::fp16_of(1.0), Float::FP16(0x3c00));
+        assert_eq!(Float::fp16_of(-4.0), Float::FP16(0xc400));
+        assert_eq!(Float::fp16_of(65504.0), Float::FP16(0x7bff));
+        assert_eq!(Float::fp16_of(100000.0), Float::FP16(0x7c00));
+        assert_eq!(Float::fp16_of(f32::NEG_INFINITY), Float::FP16(0xfc00));
+        assert!(Float::fp16_of(f32::NAN).to_f64().is_nan());
+        assert_eq!(Float::fp16_of(2f32.powi(-24)), Float::FP16(0x0001));
+        assert_eq!(Float::fp16_of(1e-10), Float::FP16(0x0000));
+        // ties to even
+        assert_eq!(Float::fp16_of(1.0 + 2f32.powi(-11)), Float::FP16(0x3c00));
+        assert_eq!(
+            Float::fp16_of(1.0 + 3.0 * 2f32.powi(-11)),
+            Float::FP16(0x3c02)
+        );
+        assert_eq!(Float::fp16_of(3.0 * 2f32.powi(-26)), Float::FP16(0x0001));
+        assert_eq!(Float::fp16_of(3.0 * 2f32.powi(-25)), Float::FP16(0x0002));
+        // rounding carry up to the exponent
+        assert_eq!(Float::fp16_of(65520.0), Float::FP16(0x7c00));
+
+        assert_eq!(Float::fp32_of(1.0), Float::FP32(0x3f80_0000));
+        assert_eq!(Float::fp64_of(1.0), Float::FP64(0x3ff0_0000_0000_0000));
+
+        let mut writer = crate::Writer::new();
+        writer.float(Float::fp64_of(1.0));
+        assert_eq!(
+            writer.finalize(),
+            [0xfb, 0x3f, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn canonical_bits() {
+        assert_eq!(Float::FP16(0x3c00).canonical_bits(), 1.0f64.to_bits());
+        assert_eq!(Float::FP32(0x3f80_0000).canonical_bits(), 1.0f64.to_bits());
+        assert_eq!(Float::FP16(0x8000).canonical_bits(), (-0.0f64).to_bits());
+        assert_eq!(
+            Float::FP16(0x7e01).canonical_bits(),
+            Float::FP64(0x7ff0_0000_0000_0001).canonical_bits()
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use super::structure::StructureLength;
+
+/// Array of T, keeping the length encoding of the CBOR Array
+///
+/// Contrary to a `Vec<T>`, which is always encoded as a canonical definite array,
+/// a List decoded from an indefinite or non-canonical array is re-encoded the same way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct List<T> {
+    pub(crate) len_encoding: StructureLength,
+    pub(crate) elements: Vec<T>,
+}
+
+impl<T> List<T> {
+    /// Create a List encoded as a canonical definite array
+    pub fn new(elements: Vec<T>) -> Self {
+        Self {
+            len_encoding: StructureLength::from(elements.len() as u64),
+            elements,
+        }
+    }
+
+    /// Create a List encoded as an indefinite array
+    pub fn indefinite(elements: Vec<T>) -> Self {
+        Self {
+            len_encoding: StructureLength::Indefinite,
+            elements,
+        }
+    }
+
+    /// Get the structure length of the List
+    pub fn struct_len(&self) -> StructureLength {
+        self.len_encoding
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.elements
+    }
+
+    /// Get mutable access to the elements
+    ///
+    /// If the number of elements changes, a definite List is encoded
+    /// with the canonical length of the new number of elements
+    pub fn elements_mut(&mut self) -> &mut Vec<T> {
+        &mut self.elements
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.elements
+    }
+}
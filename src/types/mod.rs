@@ -1,14 +1,22 @@
 use crate::lowlevel::lead::Lead;
 
+mod bounded;
+mod fixed;
 mod float;
+mod list;
 mod scalar;
 mod streamable;
 mod structure;
+mod value;
 
+pub use bounded::BoundedBytes;
+pub use fixed::Fixed;
 pub use float::Float;
+pub use list::List;
 pub use scalar::*;
 pub use streamable::*;
 pub use structure::*;
+pub use value::{ReservedSimpleValue, SimpleValue, Value};
 
 /// One of CBOR possible type
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -39,11 +47,17 @@ pub enum Type {
     Float,
     /// CBOR Byte (isomorphic to a u8)
     Byte,
+    /// CBOR Simple value from 32 to 255, in the one byte extension form
+    Simple,
     /// CBOR Break (not an element, just marking the end of a indefinite array, map, bytes, text)
     Break,
 }
 
 impl Type {
+    /// Get the type associated with a lead byte
+    ///
+    /// Note that the lead byte of the one byte extension of major type 7 is always
+    /// considered a `Type::Byte`, as `Type::Simple` also depends on the following byte
     pub fn from_lead(ld: Lead) -> Type {
         match ld {
             Lead::Positive(_) => Type::Positive,
@@ -74,6 +88,7 @@ pub enum Data<'a> {
     Negative(Negative),
     Float(Float),
     Byte(Byte),
+    Simple(u8),
     Bytes(Bytes<'a>),
     Text(Text<'a>),
     Array(Array<'a>),
@@ -92,6 +107,7 @@ pub enum DataOwned {
     Negative(Negative),
     Float(Float),
     Byte(Byte),
+    Simple(u8),
     Bytes(BytesOwned),
     Text(TextOwned),
     Array(ArrayOwned),
@@ -103,6 +119,25 @@ pub enum DataOwned {
     Undefined,
 }
 
+/// One CBOR Data element where arrays, maps and tags contents are recursively decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataTree<'a> {
+    Positive(Positive),
+    Negative(Negative),
+    Float(Float),
+    Byte(Byte),
+    Simple(u8),
+    Bytes(Bytes<'a>),
+    Text(Text<'a>),
+    Array(StructureLength, Vec<DataTree<'a>>),
+    Map(StructureLength, Vec<(DataTree<'a>, DataTree<'a>)>),
+    Tag(TagValue, Box<DataTree<'a>>),
+    True,
+    False,
+    Null,
+    Undefined,
+}
+
 impl DataOwned {
     pub fn borrow<'a>(&'a self) -> Data<'a> {
         match self {
@@ -110,6 +145,7 @@ impl DataOwned {
             DataOwned::Negative(v) => Data::Negative(*v),
             DataOwned::Float(v) => Data::Float(*v),
             DataOwned::Byte(v) => Data::Byte(*v),
+            DataOwned::Simple(v) => Data::Simple(*v),
             DataOwned::Bytes(v) => Data::Bytes(v.borrow()),
             DataOwned::Text(v) => Data::Text(v.borrow()),
             DataOwned::Array(v) => Data::Array(v.borrow()),
@@ -130,6 +166,7 @@ impl<'a> Data<'a> {
             Data::Negative(v) => DataOwned::Negative(*v),
             Data::Float(v) => DataOwned::Float(*v),
             Data::Byte(v) => DataOwned::Byte(*v),
+            Data::Simple(v) => DataOwned::Simple(*v),
             Data::Bytes(v) => DataOwned::Bytes(v.owned()),
             Data::Text(v) => DataOwned::Text(v.owned()),
             Data::Array(v) => DataOwned::Array(v.owned()),
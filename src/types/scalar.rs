@@ -16,6 +16,8 @@ pub enum Scalar {
 }
 
 /// CBOR Byte value
+///
+/// Note that the one byte extension form with a value from 32 is read as a simple value instead
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Byte(pub(crate) HeaderValue8);
 
@@ -39,6 +41,13 @@ impl Positive {
         self.0.to_u64()
     }
 
+    /// Create a Positive element from a header value
+    ///
+    /// The value encoding is kept as is, which allows to build non-canonical elements
+    pub fn from_header_value(v: HeaderValue) -> Self {
+        Self(v)
+    }
+
     /// Create a canonical Positive element from a u64,
     /// taking the smallest possible CBOR representation
     pub fn canonical(v: u64) -> Self {
@@ -50,6 +59,11 @@ impl Positive {
     pub fn is_canonical(&self) -> bool {
         self.0.is_canonical()
     }
+
+    /// Get the same Positive value with the smallest representation possible
+    pub fn to_canonical(self) -> Self {
+        Self(self.0.to_canonical())
+    }
 }
 
 impl Negative {
@@ -77,6 +91,13 @@ impl Negative {
             .and_then(|v| (-1i64).checked_sub(v))
     }
 
+    /// Create a Negative element from a header value, representing the CBOR integer -1 - value
+    ///
+    /// The value encoding is kept as is, which allows to build non-canonical elements
+    pub fn from_header_value(v: HeaderValue) -> Self {
+        Self(v)
+    }
+
     /// Create a canonical Negative element from a u64 that represent the CBOR integer -1 - value,
     /// taking the smallest possible CBOR representation
     ///
@@ -90,6 +111,11 @@ impl Negative {
     pub fn is_canonical(self) -> bool {
         self.0.is_canonical()
     }
+
+    /// Get the same Negative value with the smallest representation possible
+    pub fn to_canonical(self) -> Self {
+        Self(self.0.to_canonical())
+    }
 }
 
 impl Scalar {
@@ -159,11 +185,24 @@ impl Byte {
         self.0.to_u8()
     }
 
+    /// Create a Byte in its smallest representation
+    ///
+    /// Panics if the value is 32 or more, which is a simple value (see `Data::Simple`
+    /// and `Writer::simple`) as the reader would read it back
     pub fn canonical(v: u8) -> Self {
+        assert!(v < 0x20, "byte {} is a simple value", v);
         Byte(HeaderValue8::canonical(v))
     }
 
     pub fn is_canonical(self) -> bool {
         self.0.is_canonical()
     }
+
+    /// Get the same Byte value with the smallest representation possible
+    ///
+    /// Note that values from 24 to 31 have no canonical representation,
+    /// and are returned in the one byte extension form
+    pub fn to_canonical(self) -> Self {
+        Self(self.0.to_canonical())
+    }
 }
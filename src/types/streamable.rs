@@ -1,4 +1,5 @@
 use super::super::header::HeaderValue;
+use super::super::reader::ReaderError;
 
 /// CBOR Bytestream (indefinite and definite) with reference to the bytes
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -62,6 +63,16 @@ impl<'a> Bytes<'a> {
         }
     }
 
+    /// Same as `to_vec`, but return an error instead of allocating if the total
+    /// length of the bytes is greater than `max`
+    pub fn to_vec_bounded(&self, max: usize) -> Result<Vec<u8>, ReaderError> {
+        let len = self.len();
+        if len > max {
+            return Err(ReaderError::TooLarge { max, got: len });
+        }
+        Ok(self.to_vec())
+    }
+
     pub fn from_slice(slice: &'a [u8]) -> Self {
         Bytes::Imm(BytesData::from_slice(slice))
     }
@@ -87,6 +98,19 @@ impl<'a> Text<'a> {
         }
     }
 
+    /// Same as `to_string`, but return an error instead of allocating if the total
+    /// length in bytes of the text is greater than `max`
+    pub fn to_string_bounded(&self, max: usize) -> Result<String, ReaderError> {
+        let len = match self {
+            Text::Imm(td) => td.1.len(),
+            Text::Chunks(chunks) => chunks.iter().fold(0, |acc, c| acc + c.1.len()),
+        };
+        if len > max {
+            return Err(ReaderError::TooLarge { max, got: len });
+        }
+        Ok(self.to_string())
+    }
+
     pub fn to_str_chunks(&self) -> Vec<&'a str> {
         match self {
             Text::Imm(td) => vec![td.as_ref()],
@@ -204,3 +228,55 @@ impl TextOwned {
         TextOwned::Imm(TextDataOwned::from_string(string))
     }
 }
+
+// compare the logical content of a sequence of chunks with a contiguous slice,
+// without having to concatenate the chunks
+fn chunks_eq<'b, I: Iterator<Item = &'b [u8]>>(chunks: I, mut other: &[u8]) -> bool {
+    for chunk in chunks {
+        match other.strip_prefix(chunk) {
+            None => return false,
+            Some(rem) => other = rem,
+        }
+    }
+    other.is_empty()
+}
+
+impl<'a> PartialEq<[u8]> for Bytes<'a> {
+    fn eq(&self, other: &[u8]) -> bool {
+        match self {
+            Bytes::Imm(bd) => bd.1 == other,
+            Bytes::Chunks(chunks) => chunks_eq(chunks.iter().map(|c| c.1), other),
+        }
+    }
+}
+
+impl PartialEq<[u8]> for BytesOwned {
+    fn eq(&self, other: &[u8]) -> bool {
+        match self {
+            BytesOwned::Imm(bd) => bd.1 == other,
+            BytesOwned::Chunks(chunks) => chunks_eq(chunks.iter().map(|c| c.1.as_slice()), other),
+        }
+    }
+}
+
+impl<'a> PartialEq<str> for Text<'a> {
+    fn eq(&self, other: &str) -> bool {
+        match self {
+            Text::Imm(td) => td.1 == other,
+            Text::Chunks(chunks) => {
+                chunks_eq(chunks.iter().map(|c| c.1.as_bytes()), other.as_bytes())
+            }
+        }
+    }
+}
+
+impl PartialEq<str> for TextOwned {
+    fn eq(&self, other: &str) -> bool {
+        match self {
+            TextOwned::Imm(td) => td.1 == other,
+            TextOwned::Chunks(chunks) => {
+                chunks_eq(chunks.iter().map(|c| c.1.as_bytes()), other.as_bytes())
+            }
+        }
+    }
+}
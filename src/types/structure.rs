@@ -1,7 +1,7 @@
 use super::super::decode::{Decode, DecodeError, DecodeErrorKind};
 use super::super::encode::Encode;
 use super::super::header::HeaderValue;
-use super::super::prim::{CborData, CborSlice};
+use super::super::prim::{CborData, CborSlice, CborSliceOf};
 use super::super::reader::{Reader, ReaderError};
 use super::super::writer::Writer;
 use std::borrow::{Borrow, ToOwned};
@@ -19,6 +19,15 @@ impl StructureLength {
             StructureLength::Definite(_) => false,
         }
     }
+
+    /// Get the same length with the smallest representation possible,
+    /// an indefinite length is kept indefinite
+    pub fn to_canonical(self) -> Self {
+        match self {
+            StructureLength::Indefinite => StructureLength::Indefinite,
+            StructureLength::Definite(v) => StructureLength::Definite(v.to_canonical()),
+        }
+    }
 }
 
 impl From<Option<HeaderValue>> for StructureLength {
@@ -55,6 +64,11 @@ pub struct ArrayBuilder {
     elements: Vec<CborData>,
 }
 
+/// CBOR Map builder, when constructing
+pub struct MapBuilder {
+    elements: Vec<(CborData, CborData)>,
+}
+
 /// CBOR Map with references to keys and values
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Map<'a> {
@@ -103,6 +117,11 @@ impl<'a> Array<'a> {
         self.elements.iter().map(|v| v.reader())
     }
 
+    /// Get the reader of the element at index `i`, or None if the index is out of bounds
+    pub fn get(&self, i: usize) -> Option<Reader<'a>> {
+        self.elements.get(i).map(|v| v.reader())
+    }
+
     /// Try to turn an array of CBOR element into a homogenous Vec of T,
     /// where each reader is processed with the function f in parameter
     pub fn to_vec<F, T: Decode>(&self, f: F) -> Result<Vec<T>, DecodeErrorKind>
@@ -219,6 +238,67 @@ impl ArrayBuilder {
     }
 }
 
+impl MapBuilder {
+    /// Create a new map builder
+    pub fn new() -> Self {
+        Self { elements: vec![] }
+    }
+
+    /// Append a new key and value data into the builder
+    pub fn append(&mut self, key: CborData, value: CborData) {
+        self.elements.push((key, value))
+    }
+
+    /// Add an encoded K and V pair in the map
+    pub fn insert<K: Encode, V: Encode>(&mut self, key: &K, value: &V) {
+        let mut writer_k = Writer::new();
+        writer_k.encode(key);
+        let mut writer_v = Writer::new();
+        writer_v.encode(value);
+        self.append(writer_k.finalize_data(), writer_v.finalize_data())
+    }
+
+    /// Add an integer keyed entry only if the value is present,
+    /// so that an absent optional field is omitted from the map
+    pub fn insert_optional<V: Encode>(&mut self, key: u64, value: &Option<V>) {
+        if let Some(value) = value {
+            self.insert(&key, value)
+        }
+    }
+
+    /// Return the number of entries in the builder
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Return whether the builder has no entries
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Terminate the map into 1 finite map, of the length of the number of entries
+    pub fn finite(self) -> MapOwned {
+        MapOwned {
+            len_encoding: StructureLength::from(self.elements.len() as u64),
+            elements: self.elements,
+        }
+    }
+
+    /// Terminate the map into indefinite map
+    pub fn indefinite(self) -> MapOwned {
+        MapOwned {
+            len_encoding: StructureLength::Indefinite,
+            elements: self.elements,
+        }
+    }
+}
+
+impl Default for MapBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> std::ops::Index<usize> for Array<'a> {
     type Output = &'a CborSlice;
 
@@ -251,6 +331,43 @@ impl<'a> Map<'a> {
         self.elements.iter().map(|(k, v)| (k.reader(), v.reader()))
     }
 
+    /// Get the readers of the key and value at index `i`, or None if the index is out of bounds
+    pub fn get_entry(&self, i: usize) -> Option<(Reader<'a>, Reader<'a>)> {
+        self.elements.get(i).map(|(k, v)| (k.reader(), v.reader()))
+    }
+
+    /// Decode the key and value at index `i`, with the `key` or `value` label in the error context
+    ///
+    /// Each of the key and value needs to be fully consumed by the decoding,
+    /// and an index out of bounds fails with an `UnexpectedLength` error
+    pub fn decode_entry<K: Decode, V: Decode>(&self, i: usize) -> Result<(K, V), DecodeError> {
+        let (k, v) = self.elements.get(i).ok_or_else(|| {
+            DecodeErrorKind::UnexpectedLength {
+                expected: i + 1,
+                got: self.len(),
+            }
+            .context::<Self>()
+        })?;
+        let key = k.decode_field("key").map_err(|e| e.push::<Self>())?;
+        let value = v.decode_field("value").map_err(|e| e.push::<Self>())?;
+        Ok((key, value))
+    }
+
+    /// Decode all the keys of the Map, in the order they appear in the encoded data
+    ///
+    /// This allows to check that the keys were not reordered compared to an expected order,
+    /// e.g. before verifying a signature over the original bytes
+    pub fn key_order<K: Decode>(&self) -> Result<Vec<K>, DecodeError> {
+        self.elements
+            .iter()
+            .enumerate()
+            .map(|(i, (k, _))| {
+                k.decode()
+                    .map_err(|e| e.push_string(format!("key {}", i)).push::<Self>())
+            })
+            .collect()
+    }
+
     /// Get an iterator to the reader of each keys of the Map
     pub fn keys(&'a self) -> impl Iterator<Item = Reader<'a>> {
         self.elements.iter().map(|(k, _v)| (k.reader()))
@@ -285,6 +402,50 @@ impl<'a> Map<'a> {
         Ok(output)
     }
 
+    /// Decode a map with integer keys into a Vec, where each value is placed at the index
+    /// of its key and missing keys are left as None
+    ///
+    /// The Vec has one element more than the largest key present (empty for an empty map),
+    /// so `max_key` bounds its size. Fails if a key is not an integer, is greater than `max_key`,
+    /// or appears more than once, and if the Vec cannot be represented (a key is `usize::MAX`)
+    pub fn to_indexed_vec<V: Decode>(&self, max_key: usize) -> Result<Vec<Option<V>>, DecodeError> {
+        // validate all the keys first, so that the Vec is only as large as the data
+        let mut indexes = Vec::with_capacity(self.elements.len());
+        for (k, _) in self.elements.iter() {
+            let key = <u64>::decode(&mut k.reader()).map_err(|e| e.push::<Self>())?;
+            match usize::try_from(key) {
+                Ok(index) if index <= max_key => indexes.push(index),
+                _ => {
+                    return Err(DecodeErrorKind::OutOfRange {
+                        min: 0,
+                        max: max_key as u64,
+                        got: key,
+                    }
+                    .context::<Self>())
+                }
+            }
+        }
+        let len = match indexes.iter().max() {
+            None => 0,
+            Some(max) => max.checked_add(1).ok_or_else(|| {
+                DecodeErrorKind::Custom(format!("key {} too large", max)).context::<Self>()
+            })?,
+        };
+        let mut output = Vec::with_capacity(len);
+        output.resize_with(len, || None);
+        for (index, (_, v)) in indexes.into_iter().zip(self.elements.iter()) {
+            if output[index].is_some() {
+                return Err(
+                    DecodeErrorKind::Custom(format!("duplicate key {}", index)).context::<Self>()
+                );
+            }
+            let value = <V>::decode(&mut v.reader())
+                .map_err(|e| e.push_string(format!("{}", index)).push::<Self>())?;
+            output[index] = Some(value);
+        }
+        Ok(output)
+    }
+
     /// Turn a Map into an Owned Map
     pub fn owned(&self) -> MapOwned {
         MapOwned {
@@ -342,6 +503,27 @@ impl MapOwned {
         Ok(output)
     }
 
+    /// Sort the map entries in the RFC 8949 core deterministic order, which is the bytewise
+    /// lexicographic order of the encoded keys
+    ///
+    /// For example the keys `10, 100, -1, "z", "aa", [100], [-1], false` are in this order
+    pub fn sort_rfc8949(&mut self) {
+        self.elements
+            .sort_by(|(k1, _), (k2, _)| k1.as_ref().cmp(k2.as_ref()))
+    }
+
+    /// Sort the map entries in the length-first order of RFC 7049 canonical CBOR (also used by
+    /// CTAP2), where shorter encoded keys come first, and keys of the same length are sorted
+    /// in bytewise lexicographic order
+    ///
+    /// For example the keys `10, -1, false, 100, "z", [-1], "aa", [100]` are in this order
+    pub fn sort_length_first(&mut self) {
+        self.elements.sort_by(|(k1, _), (k2, _)| {
+            let (k1, k2) = (k1.as_ref(), k2.as_ref());
+            k1.len().cmp(&k2.len()).then_with(|| k1.cmp(k2))
+        })
+    }
+
     pub fn borrow<'a>(&'a self) -> Map<'a> {
         Map {
             len_encoding: self.len_encoding.clone(),
@@ -375,6 +557,11 @@ impl TagValue {
     pub fn from_u64(v: u64) -> Self {
         Self(HeaderValue::canonical(v))
     }
+
+    /// Get the same Tag value with the smallest representation possible
+    pub fn to_canonical(self) -> Self {
+        Self(self.0.to_canonical())
+    }
 }
 
 impl<'a> Tag<'a> {
@@ -390,6 +577,10 @@ impl<'a> Tag<'a> {
         &self.data
     }
 
+    /// Get a raw reader over the tagged element
+    ///
+    /// Note that nothing checks that the reader is finished once the caller is done with it,
+    /// prefer `read_data`, `read_contents` or `decode_data` which reject trailing data
     pub fn reader(&self) -> Reader<'a> {
         self.data.reader()
     }
@@ -404,16 +595,37 @@ impl<'a> Tag<'a> {
         Ok(t)
     }
 
+    /// Read the tagged element with a closure that can do multiple reads and decodes,
+    /// and check that all the tagged data has been consumed once the closure returns
+    pub fn read_contents<F, T>(&self, f: F) -> Result<T, DecodeError>
+    where
+        F: FnOnce(&mut Reader<'a>) -> Result<T, DecodeError>,
+    {
+        let mut reader: Reader<'a> = self.data.reader();
+        let t = f(&mut reader)?;
+        reader
+            .expect_finished()
+            .map_err(DecodeErrorKind::not_finished)
+            .map_err(|e| e.context::<Self>())?;
+        Ok(t)
+    }
+
     pub fn decode_data<T: Decode>(&self) -> Result<T, DecodeError> {
         let mut reader: Reader<'a> = self.data.reader();
         let t = <T>::decode(&mut reader)?;
         reader
             .expect_finished()
-            .map_err(DecodeErrorKind::ReaderError)
+            .map_err(DecodeErrorKind::not_finished)
             .map_err(|e| e.context::<Self>())?;
         Ok(t)
     }
 
+    /// Validate that the tagged element is a valid T, and return the typed view
+    /// of the tagged element borrowed from the original data, without copying it
+    pub fn as_typed<T: Decode>(&self) -> Result<&'a CborSliceOf<T>, DecodeError> {
+        self.data.validate_as::<T>()
+    }
+
     pub fn owned(&self) -> TagOwned {
         TagOwned {
             tag_val: self.tag_val,
@@ -0,0 +1,265 @@
+use super::DataTree;
+
+/// Simple value that can be held by a `Value`, any value except the ones from 20 to 31
+///
+/// The values from 20 to 23 are false, true, null and undefined, and the values from
+/// 24 to 31 cannot be encoded (RFC 8949 section 3.3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimpleValue(u8);
+
+impl SimpleValue {
+    /// Create a simple value, or None if the value is between 20 and 31
+    pub fn new(v: u8) -> Option<Self> {
+        if (0x14..0x20).contains(&v) {
+            None
+        } else {
+            Some(SimpleValue(v))
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        self.0
+    }
+}
+
+/// Simple value from 20 to 31 found when converting to a `Value`, which only
+/// happens with the two bytes encoding of these values that is not well-formed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReservedSimpleValue(pub u8);
+
+/// High level owned CBOR value, for processing CBOR data without a typed structure
+///
+/// Contrary to `DataOwned`, the encoding details (non canonical integers,
+/// indefinite structures, float precision) are not kept, and the value is
+/// always encoded in its canonical form
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Unsigned integer between `0` and `2^64-1`
+    Unsigned(u64),
+    /// Negative integer representing `-1 - n`
+    Negative(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Tag(u64, Box<Value>),
+    Float(f64),
+    Bool(bool),
+    Null,
+    Undefined,
+    /// Simple value, other than the ones used for booleans, null and undefined,
+    /// see `Value::simple`
+    Simple(SimpleValue),
+}
+
+impl Value {
+    /// Create an unsigned integer value
+    pub fn uint(v: u64) -> Self {
+        Value::Unsigned(v)
+    }
+
+    /// Create an integer value, either unsigned or negative
+    pub fn int(v: i64) -> Self {
+        if v >= 0 {
+            Value::Unsigned(v as u64)
+        } else {
+            Value::Negative(!v as u64)
+        }
+    }
+
+    /// Create a bytes value
+    pub fn bytes(v: &[u8]) -> Self {
+        Value::Bytes(v.to_vec())
+    }
+
+    /// Create a text value
+    pub fn text(v: &str) -> Self {
+        Value::Text(v.to_string())
+    }
+
+    /// Create an array value
+    pub fn array(v: Vec<Value>) -> Self {
+        Value::Array(v)
+    }
+
+    /// Create a map value, the entries are kept in the given order
+    pub fn map(v: Vec<(Value, Value)>) -> Self {
+        Value::Map(v)
+    }
+
+    /// Create a tagged value
+    pub fn tag(tag: u64, v: Value) -> Self {
+        Value::Tag(tag, Box::new(v))
+    }
+
+    /// Create a simple value, where the values 20 to 23 are false, true, null and undefined
+    ///
+    /// Return None for the values 24 to 31, which cannot be encoded
+    pub fn simple(v: u8) -> Option<Self> {
+        match v {
+            0x14 => Some(Value::Bool(false)),
+            0x15 => Some(Value::Bool(true)),
+            0x16 => Some(Value::Null),
+            0x17 => Some(Value::Undefined),
+            _ => SimpleValue::new(v).map(Value::Simple),
+        }
+    }
+
+    /// Get the unsigned integer, if the value is one
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Unsigned(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Get the integer, if the value is one that fits in a i64
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Unsigned(v) => i64::try_from(*v).ok(),
+            Value::Negative(v) => i64::try_from(*v).ok().map(|v| !v),
+            _ => None,
+        }
+    }
+
+    /// Get the text, if the value is one
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Text(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get the bytes, if the value is one
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get the elements, if the value is an array
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get the entries, if the value is a map
+    pub fn as_map(&self) -> Option<&[(Value, Value)]> {
+        match self {
+            Value::Map(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get the value associated with the first key equal to `key`, if the value is a map
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        self.as_map()?
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// Return whether the value is null
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+}
+
+impl<'a> TryFrom<DataTree<'a>> for Value {
+    type Error = ReservedSimpleValue;
+
+    fn try_from(tree: DataTree<'a>) -> Result<Self, Self::Error> {
+        let simple = |v: u8| {
+            SimpleValue::new(v)
+                .map(Value::Simple)
+                .ok_or(ReservedSimpleValue(v))
+        };
+        Ok(match tree {
+            DataTree::Positive(v) => Value::Unsigned(v.to_u64()),
+            DataTree::Negative(v) => Value::Negative(v.negative_u64()),
+            DataTree::Float(v) => Value::Float(v.to_f64()),
+            DataTree::Byte(v) => simple(v.to_u8())?,
+            DataTree::Simple(v) => simple(v)?,
+            DataTree::Bytes(v) => Value::Bytes(v.to_vec()),
+            DataTree::Text(v) => Value::Text(v.to_string()),
+            DataTree::Array(_, v) => Value::Array(
+                v.into_iter()
+                    .map(Value::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            DataTree::Map(_, v) => Value::Map(
+                v.into_iter()
+                    .map(|(k, v)| Ok((Value::try_from(k)?, Value::try_from(v)?)))
+                    .collect::<Result<_, _>>()?,
+            ),
+            DataTree::Tag(tag, v) => Value::Tag(tag.to_u64(), Box::new(Value::try_from(*v)?)),
+            DataTree::True => Value::Bool(true),
+            DataTree::False => Value::Bool(false),
+            DataTree::Null => Value::Null,
+            DataTree::Undefined => Value::Undefined,
+        })
+    }
+}
+
+impl From<u64> for Value {
+    fn from(v: u64) -> Self {
+        Value::uint(v)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(v: i64) -> Self {
+        Value::int(v)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Value::Bool(v)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Value::Float(v)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::text(v)
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::Text(v)
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(v: &[u8]) -> Self {
+        Value::bytes(v)
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(v: Vec<u8>) -> Self {
+        Value::Bytes(v)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(v: Vec<Value>) -> Self {
+        Value::Array(v)
+    }
+}
+
+impl From<Vec<(Value, Value)>> for Value {
+    fn from(v: Vec<(Value, Value)>) -> Self {
+        Value::Map(v)
+    }
+}
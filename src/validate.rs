@@ -62,8 +62,8 @@ impl<'a> Validator<'a> {
         self.reader.peek(context, offset, n).map_err(|e| e.into())
     }
 
+    /// Create a new validator over `data`, which can be empty
     pub fn new(data: &'a [u8]) -> Self {
-        assert!(data.len() > 0);
         let reader = CborDataReader::new(data);
         Self {
             reader,
@@ -144,6 +144,7 @@ impl<'a> Validator<'a> {
             Header::Tag(_) => self.state.tag()?,
             Header::Constant(_) => self.state.simple()?,
             Header::Byte(_) => self.state.simple()?,
+            Header::Simple(_) => self.state.simple()?,
             Header::Float(_) => self.state.simple()?,
             Header::Break => self.state.brk()?,
         };
@@ -243,6 +244,19 @@ mod tests {
         )
     }
 
+    #[test]
+    fn empty() {
+        let e = validate_error!(&[]);
+        assert!(matches!(
+            e,
+            ValidateError::DataMissing(CborDataMissing {
+                expecting_bytes: 1,
+                got_bytes: 0,
+                context: CborDataContext::Header,
+            })
+        ));
+    }
+
     #[test]
     fn array_array() {
         validate_all!(&[0x83, 0x01, 0x82, 0x02, 0x03, 0x82, 0x04, 0x05])
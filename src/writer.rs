@@ -4,6 +4,12 @@ use super::prim::CborData;
 use super::types::*;
 use crate::lowlevel::lead::*;
 
+/// Integer that cannot be represented as a CBOR Positive or Negative value
+///
+/// Integers outside of -2^64 to 2^64-1 need to be encoded as a bignum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntegerOutOfRange(pub i128);
+
 /// CBOR Data structure to write CBOR elements to a growing byte vector
 pub struct Writer {
     data: Vec<u8>,
@@ -32,6 +38,12 @@ impl Writer {
         self.data
     }
 
+    /// Get the bytes written so far
+    #[cfg(feature = "digest")]
+    pub(crate) fn written(&self) -> &[u8] {
+        &self.data
+    }
+
     /// Write a T encodable type in the writer
     pub fn encode<T: Encode + ?Sized>(&mut self, t: &T) {
         t.encode(self)
@@ -61,6 +73,16 @@ impl Writer {
         };
     }
 
+    /// Append a raw header made of a major type and a value, without any content
+    ///
+    /// This is an advanced API, for testing decoders robustness or building extensions:
+    /// nothing is checked, so the caller is responsible for following the header with the
+    /// expected content (e.g. the bytes of a Bytes header, or the elements of an Array header),
+    /// otherwise the written data is not valid CBOR
+    pub fn write_header(&mut self, major: Major, value: HeaderValue) {
+        self.write_value(major, value)
+    }
+
     fn write_value_stream(&mut self, m: Major, v: HeaderValueStream) {
         let lead = m.to_high_bits() | ContentStream::from(v.map(|c| c.to_lead_content())).to_byte();
         self.append_byte(lead);
@@ -90,6 +112,22 @@ impl Writer {
         self.write_value(Major::Negative, d.0)
     }
 
+    /// Append a Positive value in the writer with the exact encoding width of the header value
+    ///
+    /// This bypass canonicalization, and can be freely mixed with canonical writes,
+    /// for example to produce an element with some specific non-canonical integers
+    pub fn positive_raw(&mut self, v: HeaderValue) {
+        self.positive(Positive::from_header_value(v))
+    }
+
+    /// Append a Negative value (representing the integer -1 - value) in the writer with
+    /// the exact encoding width of the header value
+    ///
+    /// As `positive_raw`, this bypass canonicalization and can be mixed with canonical writes
+    pub fn negative_raw(&mut self, v: HeaderValue) {
+        self.negative(Negative::from_header_value(v))
+    }
+
     pub fn scalar(&mut self, d: Scalar) {
         match d {
             Scalar::Positive(p) => self.positive(p),
@@ -97,6 +135,23 @@ impl Writer {
         }
     }
 
+    /// Append an integer in the writer, as a canonical Positive or Negative value
+    /// depending on its sign
+    ///
+    /// Return an error if the integer is not representable in CBOR, which is
+    /// the range -2^64 to 2^64-1
+    pub fn integer(&mut self, v: i128) -> Result<(), IntegerOutOfRange> {
+        if v >= 0 {
+            let p = u64::try_from(v).map_err(|_| IntegerOutOfRange(v))?;
+            self.positive(Positive::canonical(p));
+        } else {
+            // CBOR negative n represent the integer -1 - n
+            let n = u64::try_from(-1 - v).map_err(|_| IntegerOutOfRange(v))?;
+            self.negative(Negative::canonical(n));
+        }
+        Ok(())
+    }
+
     /// Append a Byte value in the writer
     pub fn byte(&mut self, d: Byte) {
         match d.0 {
@@ -111,6 +166,20 @@ impl Writer {
         }
     }
 
+    /// Append a Simple value in the writer, using the one byte extension form from 32 to 255
+    ///
+    /// Values below 24 are written in the immediate form, which for 20 to 23 is
+    /// false, true, null and undefined. Values from 24 to 31 have no well-formed
+    /// encoding and are written in the one byte extension form, like a non canonical `Byte`
+    pub fn simple(&mut self, v: u8) {
+        if v < 0x18 {
+            self.append_byte(0xe0 + v);
+        } else {
+            self.append_byte(0xf8);
+            self.append_byte(v);
+        }
+    }
+
     /// Append a Bytes value in the writer, depending of the Bytes CBOR encoding, it will be either
     /// represented as indefinite sequence of sequence bytes (terminated by CBOR break),
     /// or an immediate bytes sequence.
@@ -174,6 +243,24 @@ impl Writer {
         }
     }
 
+    /// Append a definite Array of already encoded elements in the writer,
+    /// copying the bytes of each element as is
+    pub fn array_of_raw(&mut self, items: &[CborData]) {
+        self.write_structure_length(Major::Array, StructureLength::from(items.len() as u64));
+        for item in items {
+            self.append_slice(&item.0);
+        }
+    }
+
+    /// Append an already encoded element in the writer, copying its bytes as is
+    ///
+    /// Appending items one after the other at the top level, without any array,
+    /// build a CBOR Sequence (RFC 8742) that `finalize` returns as the concatenation
+    /// of the items, and that can be read back with `Reader::decode_iter`
+    pub fn append_item(&mut self, d: &CborData) {
+        self.append_slice(&d.0);
+    }
+
     /// Append a Map in the writer using a closure
     pub fn map_build<F>(&mut self, len: StructureLength, f: F)
     where
@@ -213,6 +300,13 @@ impl Writer {
         f(self)
     }
 
+    /// Append the self-described CBOR magic tag (Tag 55799, encoded as `0xd9d9f7`)
+    ///
+    /// The tag applies to the next element written
+    pub fn write_magic(&mut self) {
+        self.write_value(Major::Tag, HeaderValue::U16(55799))
+    }
+
     /// Append a constant value (false, true, null, undefined) in the writer
     pub fn constant(&mut self, d: Constant) {
         match d {
@@ -249,6 +343,13 @@ impl Writer {
         }
     }
 
+    /// Append a float in the writer, using the shortest precision that represents `f` exactly
+    ///
+    /// See `Float::canonical` for the representation choice
+    pub fn float_canonical(&mut self, f: f64) {
+        self.float(Float::canonical(f))
+    }
+
     /// Append some CBOR data in the writer
     pub fn data<'a>(&mut self, d: &Data<'a>) {
         match d {
@@ -256,6 +357,7 @@ impl Writer {
             Data::Negative(v) => self.negative(*v),
             Data::Float(v) => self.float(*v),
             Data::Byte(v) => self.byte(*v),
+            Data::Simple(v) => self.simple(*v),
             Data::Bytes(v) => self.bytes(v),
             Data::Text(v) => self.text(v),
             Data::Array(v) => self.array(v),
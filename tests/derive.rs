@@ -0,0 +1,700 @@
+use cbored::{decode_from_bytes, encode_to_bytes};
+use cbored_derive::CborRepr;
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(enumtype = "enumint", variant_starts_at = 1, skipkey = 2)]
+pub enum SkippedCode {
+    One,
+    Three,
+    Four,
+}
+
+// skipkey is repeated once per skipped number
+#[allow(clippy::duplicated_attributes)]
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(enumtype = "tagvariant", variant_starts_at = 1, skipkey = 2)]
+#[cborrepr(skipkey = 3)]
+pub enum SkippedVariant {
+    One(u64),
+    Four(u64),
+}
+
+#[test]
+fn enumint_starts_at_with_skipkey() {
+    assert_eq!(encode_to_bytes(&SkippedCode::One), [0x01]);
+    assert_eq!(encode_to_bytes(&SkippedCode::Three), [0x03]);
+    assert_eq!(encode_to_bytes(&SkippedCode::Four), [0x04]);
+
+    for v in [SkippedCode::One, SkippedCode::Three, SkippedCode::Four] {
+        let bytes = encode_to_bytes(&v);
+        assert_eq!(decode_from_bytes::<SkippedCode>(&bytes).unwrap(), v);
+    }
+    assert!(decode_from_bytes::<SkippedCode>(&[0x00]).is_err());
+    assert!(decode_from_bytes::<SkippedCode>(&[0x02]).is_err());
+}
+
+#[test]
+fn tagvariant_starts_at_with_skipkeys() {
+    assert_eq!(encode_to_bytes(&SkippedVariant::One(7)), [0x82, 0x01, 0x07]);
+    assert_eq!(
+        encode_to_bytes(&SkippedVariant::Four(7)),
+        [0x82, 0x04, 0x07]
+    );
+
+    for v in [SkippedVariant::One(10), SkippedVariant::Four(20)] {
+        let bytes = encode_to_bytes(&v);
+        assert_eq!(decode_from_bytes::<SkippedVariant>(&bytes).unwrap(), v);
+    }
+    assert!(decode_from_bytes::<SkippedVariant>(&[0x82, 0x02, 0x07]).is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(enumtype = "enumint", variant_starts_at = 5)]
+pub enum OffsetCode {
+    Five,
+    Six,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(enumtype = "tagvariant", variant_starts_at = 5)]
+pub enum OffsetVariant {
+    Five(u64),
+    Six,
+}
+
+#[test]
+fn variant_starts_at_is_applied_once() {
+    assert_eq!(encode_to_bytes(&OffsetCode::Five), [0x05]);
+    assert_eq!(encode_to_bytes(&OffsetCode::Six), [0x06]);
+    assert_eq!(
+        decode_from_bytes::<OffsetCode>(&[0x05]).unwrap(),
+        OffsetCode::Five
+    );
+    assert_eq!(
+        decode_from_bytes::<OffsetCode>(&[0x06]).unwrap(),
+        OffsetCode::Six
+    );
+    assert!(decode_from_bytes::<OffsetCode>(&[0x0a]).is_err());
+
+    assert_eq!(encode_to_bytes(&OffsetVariant::Five(1)), [0x82, 0x05, 0x01]);
+    assert_eq!(encode_to_bytes(&OffsetVariant::Six), [0x81, 0x06]);
+    assert_eq!(
+        decode_from_bytes::<OffsetVariant>(&[0x82, 0x05, 0x01]).unwrap(),
+        OffsetVariant::Five(1)
+    );
+    assert_eq!(
+        decode_from_bytes::<OffsetVariant>(&[0x81, 0x06]).unwrap(),
+        OffsetVariant::Six
+    );
+    assert!(decode_from_bytes::<OffsetVariant>(&[0x81, 0x0b]).is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(enumtype = "tagvariant")]
+pub enum OptionalVariant {
+    Anon(u64, Option<u64>),
+    Named { a: u64, b: Option<String> },
+}
+
+#[test]
+fn tagvariant_last_optional() {
+    let cases = [
+        (
+            OptionalVariant::Anon(1, Some(2)),
+            vec![0x83, 0x00, 0x01, 0x02],
+        ),
+        (OptionalVariant::Anon(1, None), vec![0x82, 0x00, 0x01]),
+        (
+            OptionalVariant::Named {
+                a: 1,
+                b: Some("a".to_string()),
+            },
+            vec![0x83, 0x01, 0x01, 0x61, 0x61],
+        ),
+        (
+            OptionalVariant::Named { a: 1, b: None },
+            vec![0x82, 0x01, 0x01],
+        ),
+    ];
+    for (v, bytes) in cases {
+        assert_eq!(encode_to_bytes(&v), bytes);
+        assert_eq!(decode_from_bytes::<OptionalVariant>(&bytes).unwrap(), v);
+    }
+    assert!(decode_from_bytes::<OptionalVariant>(&[0x81, 0x00]).is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(structure = "array", borrowed)]
+pub struct BorrowedArray<'a> {
+    name: &'a str,
+    data: &'a [u8],
+    n: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(structure = "mapint", borrowed)]
+pub struct BorrowedMap<'a> {
+    #[cborrepr(mandatory)]
+    name: &'a str,
+    data: Option<&'a [u8]>,
+}
+
+#[test]
+fn borrowed_struct() {
+    let bytes = [0x83, 0x61, 0x61, 0x42, 0x01, 0x02, 0x03];
+    let v: BorrowedArray = cbored::Reader::new(&bytes).decode_borrowed().unwrap();
+    assert_eq!(
+        v,
+        BorrowedArray {
+            name: "a",
+            data: &[1, 2],
+            n: 3
+        }
+    );
+    // the borrowed fields point into the input buffer
+    assert_eq!(v.name.as_ptr(), bytes[2..].as_ptr());
+    assert_eq!(encode_to_bytes(&v), bytes);
+
+    let bytes = [0xa2, 0x00, 0x61, 0x61, 0x01, 0x41, 0x01];
+    let v: BorrowedMap = cbored::Reader::new(&bytes).decode_borrowed().unwrap();
+    assert_eq!(
+        v,
+        BorrowedMap {
+            name: "a",
+            data: Some(&[1])
+        }
+    );
+    assert_eq!(encode_to_bytes(&v), bytes);
+
+    // indefinite text cannot be borrowed
+    let bytes = [0xa1, 0x00, 0x7f, 0x61, 0x61, 0xff];
+    assert!(cbored::Reader::new(&bytes)
+        .decode_borrowed::<BorrowedMap>()
+        .is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(structure = "array")]
+pub struct Header {
+    alg: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(structure = "array")]
+pub struct Envelope {
+    #[cborrepr(bstr)]
+    protected: Header,
+    payload: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(structure = "mapint")]
+pub struct EnvelopeMap {
+    #[cborrepr(bstr)]
+    protected: Option<Header>,
+}
+
+#[test]
+fn bstr_wrapped_field() {
+    let v = Envelope {
+        protected: Header { alg: 1 },
+        payload: "a".to_string(),
+    };
+    let bytes = [0x82, 0x42, 0x81, 0x01, 0x61, 0x61];
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(decode_from_bytes::<Envelope>(&bytes).unwrap(), v);
+
+    let v = EnvelopeMap {
+        protected: Some(Header { alg: 1 }),
+    };
+    let bytes = [0xa1, 0x00, 0x42, 0x81, 0x01];
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(decode_from_bytes::<EnvelopeMap>(&bytes).unwrap(), v);
+
+    // the field is not wrapped in a bytes
+    assert!(decode_from_bytes::<Envelope>(&[0x82, 0x81, 0x01, 0x61, 0x61]).is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(structure = "mapint", accept = "array,mapint")]
+pub struct Migrated {
+    #[cborrepr(mandatory)]
+    a: u64,
+    #[cborrepr(mandatory)]
+    b: String,
+}
+
+#[test]
+fn accept_array_or_map() {
+    let v = Migrated {
+        a: 1,
+        b: "a".to_string(),
+    };
+    let map_bytes = [0xa2, 0x00, 0x01, 0x01, 0x61, 0x61];
+    assert_eq!(encode_to_bytes(&v), map_bytes);
+    assert_eq!(decode_from_bytes::<Migrated>(&map_bytes).unwrap(), v);
+
+    let array_bytes = [0x82, 0x01, 0x61, 0x61];
+    assert_eq!(decode_from_bytes::<Migrated>(&array_bytes).unwrap(), v);
+
+    assert!(decode_from_bytes::<Migrated>(&[0x01]).is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(enumtype = "strvariant")]
+pub enum Op {
+    Add(u64, String),
+    #[cborrepr(rename = "del")]
+    Remove(u64),
+    Clear,
+}
+
+#[test]
+fn strvariant() {
+    let v = Op::Add(1, "a".to_string());
+    let bytes = [0x83, 0x63, b'A', b'd', b'd', 0x01, 0x61, b'a'];
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(decode_from_bytes::<Op>(&bytes).unwrap(), v);
+
+    let v = Op::Remove(2);
+    let bytes = [0x82, 0x63, b'd', b'e', b'l', 0x02];
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(decode_from_bytes::<Op>(&bytes).unwrap(), v);
+
+    let v = Op::Clear;
+    let bytes = [0x81, 0x65, b'C', b'l', b'e', b'a', b'r'];
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(decode_from_bytes::<Op>(&bytes).unwrap(), v);
+
+    // unknown name, and the renamed variant is not known by its identifier
+    assert!(decode_from_bytes::<Op>(&[0x81, 0x61, b'x']).is_err());
+    assert!(
+        decode_from_bytes::<Op>(&[0x82, 0x66, b'R', b'e', b'm', b'o', b'v', b'e', 0x02]).is_err()
+    );
+
+    // a discriminant that is not a text is reported in the context of the enum
+    let err = decode_from_bytes::<Op>(&[0x82, 0x01, 0x02]).unwrap_err();
+    assert!(err.context().last().unwrap().ends_with("Op"));
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(structure = "array")]
+pub struct Proxied {
+    id: u64,
+    #[cborrepr(verbatim)]
+    body: cbored::CborData,
+}
+
+#[test]
+fn verbatim_field() {
+    // [1, {0: 0x18 0x02 (non canonical 2)}]
+    let bytes = [0x82, 0x01, 0xa1, 0x00, 0x18, 0x02];
+    let v = decode_from_bytes::<Proxied>(&bytes).unwrap();
+    assert_eq!(v.id, 1);
+    assert_eq!(v.body.as_ref(), &bytes[2..]);
+    assert_eq!(encode_to_bytes(&v), bytes);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(enumtype = "tagvariant", structure = "mapint")]
+pub enum Command {
+    Move {
+        #[cborrepr(mandatory)]
+        x: u64,
+        #[cborrepr(mandatory)]
+        y: u64,
+        speed: Option<u64>,
+    },
+    Stop(u64),
+}
+
+#[test]
+fn tagvariant_mapint() {
+    let v = Command::Move {
+        x: 1,
+        y: 2,
+        speed: None,
+    };
+    let bytes = [0x82, 0x00, 0xa2, 0x00, 0x01, 0x01, 0x02];
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(decode_from_bytes::<Command>(&bytes).unwrap(), v);
+
+    let v = Command::Move {
+        x: 1,
+        y: 2,
+        speed: Some(3),
+    };
+    let bytes = [0x82, 0x00, 0xa3, 0x00, 0x01, 0x01, 0x02, 0x02, 0x03];
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(decode_from_bytes::<Command>(&bytes).unwrap(), v);
+    // keys in any order
+    let bytes = [0x82, 0x00, 0xa3, 0x02, 0x03, 0x01, 0x02, 0x00, 0x01];
+    assert_eq!(decode_from_bytes::<Command>(&bytes).unwrap(), v);
+
+    // unnamed fields stay positional
+    let v = Command::Stop(4);
+    let bytes = [0x82, 0x01, 0x04];
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(decode_from_bytes::<Command>(&bytes).unwrap(), v);
+
+    // missing mandatory field, duplicated key, unknown key
+    assert!(decode_from_bytes::<Command>(&[0x82, 0x00, 0xa1, 0x00, 0x01]).is_err());
+    assert!(
+        decode_from_bytes::<Command>(&[0x82, 0x00, 0xa3, 0x00, 0x01, 0x01, 0x02, 0x00, 0x01])
+            .is_err()
+    );
+    assert!(
+        decode_from_bytes::<Command>(&[0x82, 0x00, 0xa3, 0x00, 0x01, 0x01, 0x02, 0x05, 0x01])
+            .is_err()
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(enumtype = "tagvariant", structure = "mapint")]
+pub enum Parcel {
+    #[cborrepr(map_starts_at = 1, skipkey = 2)]
+    Sealed {
+        #[cborrepr(mandatory, bstr)]
+        inner: u64,
+        #[cborrepr(verbatim)]
+        extra: Option<cbored::CborData>,
+        #[cborrepr(mandatory, key = -1)]
+        version: u64,
+    },
+}
+
+#[test]
+fn tagvariant_mapint_field_attributes() {
+    // keys start at 1, 2 is skipped and version has the explicit key -1
+    let v = Parcel::Sealed {
+        inner: 5,
+        extra: None,
+        version: 2,
+    };
+    let bytes = [0x82, 0x00, 0xa2, 0x01, 0x41, 0x05, 0x20, 0x02];
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(decode_from_bytes::<Parcel>(&bytes).unwrap(), v);
+
+    // the verbatim field keeps the non canonical encoding of 2
+    let v = Parcel::Sealed {
+        inner: 5,
+        extra: Some(decode_from_bytes::<cbored::CborData>(&[0x18, 0x02]).unwrap()),
+        version: 2,
+    };
+    let bytes = [
+        0x82, 0x00, 0xa3, 0x01, 0x41, 0x05, 0x03, 0x18, 0x02, 0x20, 0x02,
+    ];
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(decode_from_bytes::<Parcel>(&bytes).unwrap(), v);
+
+    // positional key 0 and skipped key 2 are unknown, and inner is not wrapped in bytes
+    assert!(
+        decode_from_bytes::<Parcel>(&[0x82, 0x00, 0xa2, 0x00, 0x41, 0x05, 0x20, 0x02]).is_err()
+    );
+    assert!(decode_from_bytes::<Parcel>(&[
+        0x82, 0x00, 0xa3, 0x01, 0x41, 0x05, 0x02, 0x01, 0x20, 0x02
+    ])
+    .is_err());
+    assert!(decode_from_bytes::<Parcel>(&[0x82, 0x00, 0xa2, 0x01, 0x05, 0x20, 0x02]).is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(structure = "mapint", map_starts_at = 1)]
+pub struct Shifted {
+    #[cborrepr(mandatory)]
+    a: u64,
+    b: Option<u64>,
+}
+
+#[test]
+fn mapint_map_starts_at() {
+    let v = Shifted { a: 1, b: Some(2) };
+    let bytes = [0xa2, 0x01, 0x01, 0x02, 0x02];
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(decode_from_bytes::<Shifted>(&bytes).unwrap(), v);
+    assert!(decode_from_bytes::<Shifted>(&[0xa1, 0x00, 0x01]).is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, CborRepr)]
+#[cborrepr(structure = "flat")]
+pub struct Meters(f64);
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(transparent)]
+pub struct UserId {
+    id: u64,
+}
+
+#[test]
+fn single_field_passthrough() {
+    let v = Meters(1.5);
+    let bytes = encode_to_bytes(&v);
+    assert_eq!(bytes, encode_to_bytes(&1.5f64));
+    assert_eq!(decode_from_bytes::<Meters>(&bytes).unwrap(), v);
+
+    let v = UserId { id: 10 };
+    assert_eq!(encode_to_bytes(&v), [0x0a]);
+    assert_eq!(decode_from_bytes::<UserId>(&[0x0a]).unwrap(), v);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(enumtype = "compact")]
+pub enum CompactCert {
+    Genesis,
+    Transfer(u64, Option<u64>),
+    Retire,
+}
+
+#[test]
+fn compact_enum() {
+    assert_eq!(encode_to_bytes(&CompactCert::Genesis), [0x00]);
+    assert_eq!(
+        encode_to_bytes(&CompactCert::Transfer(5, None)),
+        [0x82, 0x01, 0x05]
+    );
+    assert_eq!(encode_to_bytes(&CompactCert::Retire), [0x02]);
+
+    for v in [
+        CompactCert::Genesis,
+        CompactCert::Transfer(5, Some(6)),
+        CompactCert::Transfer(5, None),
+        CompactCert::Retire,
+    ] {
+        let bytes = encode_to_bytes(&v);
+        assert_eq!(decode_from_bytes::<CompactCert>(&bytes).unwrap(), v);
+    }
+    // variants with fields are not accepted as integer, and the other way around
+    assert!(decode_from_bytes::<CompactCert>(&[0x01]).is_err());
+    assert!(decode_from_bytes::<CompactCert>(&[0x81, 0x00]).is_err());
+    assert!(decode_from_bytes::<CompactCert>(&[0x03]).is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(structure = "maptext")]
+pub struct Profile {
+    #[cborrepr(mandatory)]
+    name: String,
+    #[cborrepr(rename = "yrs")]
+    age: Option<u64>,
+}
+
+#[test]
+fn maptext_structure() {
+    let v = Profile {
+        name: "a".to_string(),
+        age: Some(3),
+    };
+    let bytes = [
+        0xa2, 0x64, b'n', b'a', b'm', b'e', 0x61, b'a', 0x63, b'y', b'r', b's', 0x03,
+    ];
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(decode_from_bytes::<Profile>(&bytes).unwrap(), v);
+
+    // keys can be in any order
+    let bytes = [
+        0xa2, 0x63, b'y', b'r', b's', 0x03, 0x64, b'n', b'a', b'm', b'e', 0x61, b'a',
+    ];
+    assert_eq!(decode_from_bytes::<Profile>(&bytes).unwrap(), v);
+
+    // and optional keys omitted
+    let bytes = [0xa1, 0x64, b'n', b'a', b'm', b'e', 0x61, b'a'];
+    let v = Profile {
+        name: "a".to_string(),
+        age: None,
+    };
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(decode_from_bytes::<Profile>(&bytes).unwrap(), v);
+
+    // missing mandatory key, unknown key and duplicated key
+    assert!(decode_from_bytes::<Profile>(&[0xa1, 0x63, b'y', b'r', b's', 0x03]).is_err());
+    assert!(decode_from_bytes::<Profile>(&[0xa1, 0x61, b'x', 0x03]).is_err());
+    assert!(decode_from_bytes::<Profile>(&[
+        0xa2, 0x63, b'y', b'r', b's', 0x03, 0x63, b'y', b'r', b's', 0x03
+    ])
+    .is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(structure = "mapint")]
+pub struct Extensible {
+    #[cborrepr(mandatory)]
+    a: u64,
+    b: Option<String>,
+    #[cborrepr(rest)]
+    extensions: std::collections::BTreeMap<u64, cbored::CborData>,
+}
+
+#[test]
+fn mapint_rest_field() {
+    // keys 5 and 100 are unknown, and kept as is (including the non-canonical integer)
+    let bytes = [0xa3, 0x00, 0x01, 0x05, 0x19, 0x00, 0x02, 0x18, 0x64, 0xf5];
+    let v = decode_from_bytes::<Extensible>(&bytes).unwrap();
+    assert_eq!(v.a, 1);
+    assert_eq!(v.b, None);
+    assert_eq!(v.extensions.len(), 2);
+    assert_eq!(v.extensions[&5].as_ref(), [0x19, 0x00, 0x02]);
+    assert_eq!(encode_to_bytes(&v), bytes);
+
+    let v = Extensible {
+        a: 1,
+        b: Some("a".to_string()),
+        extensions: std::collections::BTreeMap::new(),
+    };
+    let bytes = encode_to_bytes(&v);
+    assert_eq!(bytes, [0xa2, 0x00, 0x01, 0x01, 0x61, 0x61]);
+    assert_eq!(decode_from_bytes::<Extensible>(&bytes).unwrap(), v);
+
+    // duplicated unknown keys are still rejected
+    assert!(decode_from_bytes::<Extensible>(&[0xa3, 0x00, 0x01, 0x05, 0x01, 0x05, 0x02]).is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(enumtype = "strenum")]
+pub enum Color {
+    Red,
+    #[cborrepr(rename = "green")]
+    Green,
+}
+
+#[test]
+fn strenum() {
+    assert_eq!(encode_to_bytes(&Color::Red), [0x63, b'R', b'e', b'd']);
+    let bytes = [0x65, b'g', b'r', b'e', b'e', b'n'];
+    assert_eq!(encode_to_bytes(&Color::Green), bytes);
+    assert_eq!(decode_from_bytes::<Color>(&bytes).unwrap(), Color::Green);
+    assert_eq!(
+        decode_from_bytes::<Color>(&[0x63, b'R', b'e', b'd']).unwrap(),
+        Color::Red
+    );
+
+    // the original name of a renamed variant is not accepted
+    assert!(decode_from_bytes::<Color>(&[0x65, b'G', b'r', b'e', b'e', b'n']).is_err());
+    assert!(decode_from_bytes::<Color>(&[0x00]).is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(structure = "mapint")]
+pub struct Ec2Key {
+    #[cborrepr(mandatory, key = 1)]
+    kty: u64,
+    #[cborrepr(mandatory, key = -1)]
+    crv: u64,
+    #[cborrepr(key = -2)]
+    x: Option<String>,
+}
+
+#[test]
+fn mapint_negative_keys() {
+    let v = Ec2Key {
+        kty: 2,
+        crv: 1,
+        x: Some("a".to_string()),
+    };
+    let bytes = [0xa3, 0x01, 0x02, 0x20, 0x01, 0x21, 0x61, b'a'];
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(decode_from_bytes::<Ec2Key>(&bytes).unwrap(), v);
+
+    // duplicated negative key, and unknown negative key
+    assert!(decode_from_bytes::<Ec2Key>(&[0xa3, 0x01, 0x02, 0x20, 0x01, 0x20, 0x01]).is_err());
+    assert!(decode_from_bytes::<Ec2Key>(&[0xa3, 0x01, 0x02, 0x20, 0x01, 0x23, 0x01]).is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(structure = "array")]
+pub struct Ping;
+
+#[test]
+fn unit_struct() {
+    assert_eq!(encode_to_bytes(&Ping), [0x80]);
+    assert_eq!(decode_from_bytes::<Ping>(&[0x80]).unwrap(), Ping);
+    assert!(decode_from_bytes::<Ping>(&[0x81, 0x00]).is_err());
+    assert!(decode_from_bytes::<Ping>(&[0xf6]).is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, CborRepr)]
+#[cborrepr(structure = "mapint")]
+pub struct ExplicitAfterPositional {
+    #[cborrepr(mandatory)]
+    a: u64,
+    #[cborrepr(mandatory, key = 5)]
+    b: u64,
+    #[cborrepr(mandatory)]
+    c: u64,
+}
+
+#[test]
+fn mapint_explicit_and_positional_keys() {
+    // explicit keys don't shift the position of the other fields
+    let v = ExplicitAfterPositional { a: 5, b: 6, c: 7 };
+    let bytes = [0xa3, 0x00, 0x05, 0x05, 0x06, 0x02, 0x07];
+    assert_eq!(encode_to_bytes(&v), bytes);
+    assert_eq!(
+        decode_from_bytes::<ExplicitAfterPositional>(&bytes).unwrap(),
+        v
+    );
+}
+
+#[derive(Debug, CborRepr)]
+#[cborrepr(structure = "array")]
+pub struct Holder {
+    data: cbored::DataOwned,
+}
+
+#[test]
+fn policy_applies_to_fields() {
+    use cbored::{Reader, ReaderPolicy};
+    let strict = ReaderPolicy {
+        reject_duplicate_keys: true,
+        ..ReaderPolicy::default()
+    };
+    // [{1: 2, 1: 3}]
+    let bytes = [0x81, 0xa2, 0x01, 0x02, 0x01, 0x03];
+    assert!(decode_from_bytes::<Holder>(&bytes).is_ok());
+    assert!(Reader::with_policy(&bytes, strict)
+        .decode::<Holder>()
+        .is_err());
+}
+
+#[derive(Debug, CborRepr)]
+#[cborrepr(structure = "mapint")]
+pub struct Labels {
+    #[cborrepr(mandatory)]
+    first: std::rc::Rc<str>,
+    #[cborrepr(mandatory)]
+    second: std::rc::Rc<str>,
+}
+
+#[derive(Debug, CborRepr)]
+#[cborrepr(structure = "array")]
+pub struct LabelsPair {
+    left: Labels,
+    right: Labels,
+}
+
+#[test]
+fn interner_applies_to_fields() {
+    use cbored::{Interner, Reader};
+    use std::rc::Rc;
+
+    let label = |s: &str| -> Rc<str> { Rc::from(s) };
+    let v = LabelsPair {
+        left: Labels {
+            first: label("name"),
+            second: label("name"),
+        },
+        right: Labels {
+            first: label("name"),
+            second: label("other"),
+        },
+    };
+    let bytes = encode_to_bytes(&v);
+
+    let interner = Interner::new();
+    let decoded = Reader::with_interner(&bytes, &interner)
+        .decode::<LabelsPair>()
+        .unwrap();
+    assert!(Rc::ptr_eq(&decoded.left.first, &decoded.left.second));
+    assert!(Rc::ptr_eq(&decoded.left.first, &decoded.right.first));
+    assert_eq!(&*decoded.right.second, "other");
+    assert_eq!(interner.len(), 2);
+}